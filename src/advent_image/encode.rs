@@ -0,0 +1,175 @@
+use crate::advent_image::{merge_layers, Image, ImageErr, Layer, Pixel};
+use crate::advent_image::export::ExportOptions;
+use image::RgbImage;
+use std::path::Path;
+
+/// The pixel a lower layer shows where a higher one is opaque, chosen to
+/// differ from what ends up visible
+fn hidden_pixel(visible: Pixel) -> Pixel {
+    match visible {
+        Pixel::Black => Pixel::White,
+        _ => Pixel::Black,
+    }
+}
+
+impl Image {
+    /// Builds a single layer image from rows of pixels, which must all have
+    /// the same length
+    pub fn from_rows(rows: &[Vec<Pixel>]) -> Result<Image, ImageErr> {
+        let width = rows.first().map(|r| r.len()).unwrap_or(0);
+
+        if width == 0 || rows.iter().any(|r| r.len() != width) {
+            return Err(ImageErr::InvalidPixelCount);
+        }
+
+        let pixels: Vec<Pixel> = rows.iter().flat_map(|r| r.iter().cloned()).collect();
+
+        Ok(Image::new_from_layers(width as u32, rows.len() as u32, &[Layer::new_from_pixels(&pixels)]))
+    }
+
+    /// Reads an image drawn with `options`, taking one sample per
+    /// `scale` by `scale` square. Every sampled colour must be in the options,
+    /// and both sides must be a whole number of squares.
+    pub fn from_rgb(rgb: &RgbImage, options: &ExportOptions) -> Result<Image, ImageErr> {
+        let scale = options.scale();
+
+        if rgb.width() % scale != 0 || rgb.height() % scale != 0 {
+            return Err(ImageErr::InvalidScale { width: rgb.width(), height: rgb.height(), scale });
+        }
+
+        let (width, height) = (rgb.width() / scale, rgb.height() / scale);
+
+        let rows = (0..height).map(|y| {
+            (0..width).map(|x| {
+                options.pixel_for(rgb.get_pixel(x * scale, y * scale).0).ok_or(ImageErr::ParseError)
+            }).collect()
+        }).collect::<Result<Vec<Vec<Pixel>>, ImageErr>>()?;
+
+        Image::from_rows(&rows)
+    }
+
+    pub fn from_png<P: AsRef<Path>>(path: P, options: &ExportOptions) -> Result<Image, ImageErr> {
+        Image::from_rgb(&image::open(path)?.to_rgb(), options)
+    }
+
+    /// Spreads the merged image over `count` layers. Each visible pixel is
+    /// placed on one of them, with transparency above it and other pixels
+    /// below it, so `merge_layers` gives back the merged image.
+    ///
+    /// ```
+    /// use aoc_2019::advent_image::{merge_layers, Image};
+    ///
+    /// let img = Image::new_from_ints(3, 1, &vec![0,1,2]).unwrap();
+    /// let split = img.split_layers(2).unwrap();
+    ///
+    /// assert_eq!(split.to_sif(), "022112");
+    /// assert_eq!(merge_layers(&split).unwrap(), merge_layers(&img).unwrap());
+    /// ```
+    pub fn split_layers(&self, count: usize) -> Result<Image, ImageErr> {
+        let merged = merge_layers(self)?;
+        let visible = merged.layers().first().ok_or(ImageErr::OutOfBounds)?.pixels();
+
+        if count == 0 {
+            return Err(ImageErr::OutOfBounds);
+        }
+
+        let layers: Vec<Layer> = (0..count).map(|l| {
+            let pixels: Vec<Pixel> = visible.iter().enumerate().map(|(i, p)| {
+                let owner = i % count;
+
+                if *p == Pixel::Transparent || l < owner {
+                    Pixel::Transparent
+                } else if l == owner {
+                    *p
+                } else {
+                    hidden_pixel(*p)
+                }
+            }).collect();
+
+            Layer::new_from_pixels(&pixels)
+        }).collect();
+
+        Ok(Image::new_from_layers(self.width(), self.height(), &layers))
+    }
+
+    /// The layers as a Space Image Format digit string
+    pub fn to_sif(&self) -> String {
+        self.layers()
+            .iter()
+            .flat_map(|l| l.pixels().iter())
+            .filter_map(|p| std::char::from_digit(p.digit(), 10))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::advent_image::{merge_layers, Image, ImageErr, Pixel};
+    use crate::advent_image::export::{ExportFormat, ExportOptions};
+    use crate::day8::input_generator;
+    use image::RgbImage;
+    use std::env;
+    use std::fs;
+
+    fn day8_image() -> Image {
+        let input = input_generator(&fs::read_to_string("input/2019/day8.txt").unwrap());
+
+        Image::new_from_ints(25, 6, &input).unwrap()
+    }
+
+    fn parse_sif(width: u32, height: u32, sif: &str) -> Image {
+        Image::new_from_ints(width, height, &input_generator(sif)).unwrap()
+    }
+
+    #[test]
+    fn test_sif_round_trip() {
+        let img = day8_image();
+
+        assert_eq!(parse_sif(25, 6, &img.to_sif()), img);
+
+        for count in 1..5 {
+            let split = img.split_layers(count).unwrap();
+            let parsed = parse_sif(25, 6, &split.to_sif());
+
+            assert_eq!(parsed.layers().len(), count);
+            assert_eq!(merge_layers(&parsed).unwrap(), merge_layers(&img).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_rows_round_trip() {
+        let img = day8_image();
+        let rows = img.day08b_challenge().unwrap();
+
+        assert_eq!(Image::from_rows(&rows).unwrap(), merge_layers(&img).unwrap());
+        assert!(Image::from_rows(&[vec![Pixel::Black], vec![]]).is_err());
+        assert!(Image::from_rows(&[]).is_err());
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let img = day8_image();
+        let options = ExportOptions::new().with_scale(4).with_color(Pixel::White, [255, 200, 0]);
+        let path = env::temp_dir().join(format!("aoc_2019_sif_{}.png", std::process::id()));
+
+        img.save(&path, ExportFormat::Png, &options).unwrap();
+        let loaded = Image::from_png(&path, &options);
+        let unknown = Image::from_png(&path, &ExportOptions::new());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), merge_layers(&img).unwrap());
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_rgb_scale_mismatch() {
+        let options = ExportOptions::new().with_scale(2);
+        let rgb = RgbImage::from_pixel(4, 3, image::Rgb(options.color(Pixel::Black)));
+
+        match Image::from_rgb(&rgb, &options) {
+            Err(ImageErr::InvalidScale { width: 4, height: 3, scale: 2 }) => (),
+            res => panic!("Expected a scale mismatch, got {:?}", res),
+        }
+        assert_eq!(Image::from_rgb(&rgb, &ExportOptions::new()).unwrap().height(), 3);
+    }
+}
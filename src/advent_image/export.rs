@@ -0,0 +1,180 @@
+use crate::advent_image::{merge_layers_with, Image, ImageErr, Layer, Palette, Pixel};
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage};
+use image::pnm::{PNMSubtype, SampleEncoding};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Png,
+    /// Binary PPM (P6)
+    Ppm,
+}
+
+/// Scale factor, the colour used for each pixel digit and the palette the
+/// layers are merged with
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    scale: u32,
+    colors: [[u8; 3]; 10],
+    palette: Palette,
+}
+
+impl Default for ExportOptions {
+    /// Black and white at 1:1, transparent pixels in grey and any other
+    /// digits in increasingly light shades of red
+    fn default() -> ExportOptions {
+        let mut colors = [[0, 0, 0]; 10];
+        colors[1] = [255, 255, 255];
+        colors[2] = [128, 128, 128];
+        for (d, color) in colors.iter_mut().enumerate().skip(3) {
+            *color = [255, (d as u8 - 3) * 36, (d as u8 - 3) * 36];
+        }
+
+        ExportOptions { scale: 1, colors, palette: Palette::standard() }
+    }
+}
+
+impl ExportOptions {
+    pub fn new() -> ExportOptions {
+        ExportOptions::default()
+    }
+
+    pub fn scale(&self) -> u32 { self.scale }
+
+    pub fn palette(&self) -> &Palette { &self.palette }
+
+    pub fn color(&self, pixel: Pixel) -> [u8; 3] {
+        self.colors[pixel.digit() as usize]
+    }
+
+    /// The first pixel digit drawn in this colour
+    pub fn pixel_for(&self, color: [u8; 3]) -> Option<Pixel> {
+        self.colors.iter().position(|c| *c == color).and_then(|d| Pixel::from_digit(d as u32))
+    }
+
+    /// Draws every pixel as a `scale` by `scale` square, a scale of 0 is
+    /// treated as 1
+    pub fn with_scale(&self, scale: u32) -> ExportOptions {
+        ExportOptions { scale: scale.max(1), ..self.clone() }
+    }
+
+    pub fn with_color(&self, pixel: Pixel, color: [u8; 3]) -> ExportOptions {
+        let mut colors = self.colors;
+        colors[pixel.digit() as usize] = color;
+
+        ExportOptions { colors, ..self.clone() }
+    }
+
+    /// Merges layers with `palette` rather than the standard one
+    pub fn with_palette(&self, palette: &Palette) -> ExportOptions {
+        ExportOptions { palette: palette.clone(), ..self.clone() }
+    }
+}
+
+fn layer_to_rgb(layer: &Layer, width: u32, height: u32, options: &ExportOptions) -> Result<RgbImage, ImageErr> {
+    if layer.pixels().len() != (width * height) as usize {
+        return Err(ImageErr::InvalidPixelCount);
+    }
+
+    let scale = options.scale();
+
+    Ok(RgbImage::from_fn(width * scale, height * scale, |x, y| {
+        let pixel = layer.pixels()[((x / scale) + (y / scale) * width) as usize];
+
+        Rgb(options.color(pixel))
+    }))
+}
+
+fn encode(rgb: RgbImage, format: ExportFormat) -> Result<Vec<u8>, ImageErr> {
+    let output = match format {
+        ExportFormat::Png => ImageOutputFormat::Png,
+        ExportFormat::Ppm => ImageOutputFormat::Pnm(PNMSubtype::Pixmap(SampleEncoding::Binary)),
+    };
+
+    let mut bytes = vec![];
+    DynamicImage::ImageRgb8(rgb).write_to(&mut bytes, output)?;
+
+    Ok(bytes)
+}
+
+fn save_bytes<P: AsRef<Path>>(bytes: &[u8], path: P) -> Result<(), ImageErr> {
+    File::create(path)?.write_all(bytes)?;
+
+    Ok(())
+}
+
+impl Image {
+    /// Renders the image merged with the palette in `options`
+    ///
+    /// ```
+    /// use aoc_2019::advent_image::{Compositing, Image, Palette, Pixel};
+    /// use aoc_2019::advent_image::export::ExportOptions;
+    ///
+    /// // 3 is transparent, which the standard palette doesn't know about
+    /// let palette = Palette::new(&[Compositing::Opaque, Compositing::Opaque, Compositing::Opaque, Compositing::Transparent]).unwrap();
+    /// let img = Image::new_from_ints_with(2, 1, &vec![3,3, 3,2], &palette).unwrap();
+    /// let options = ExportOptions::new().with_palette(&palette);
+    /// let rgb = img.to_rgb(&options).unwrap();
+    ///
+    /// assert_eq!(rgb.get_pixel(0, 0).0, options.color(Pixel::Other(3)));
+    /// assert_eq!(rgb.get_pixel(1, 0).0, options.color(Pixel::Transparent));
+    /// assert_eq!(img.to_rgb(&ExportOptions::new()).unwrap().get_pixel(1, 0).0, options.color(Pixel::Other(3)));
+    /// ```
+    pub fn to_rgb(&self, options: &ExportOptions) -> Result<RgbImage, ImageErr> {
+        let merged = merge_layers_with(self, options.palette())?;
+        let layer = merged.layers().first().ok_or(ImageErr::OutOfBounds)?;
+
+        layer_to_rgb(layer, self.width(), self.height(), options)
+    }
+
+    /// Renders a single layer, transparent pixels included
+    pub fn layer_to_rgb(&self, index: usize, options: &ExportOptions) -> Result<RgbImage, ImageErr> {
+        let layer = self.layers().get(index).ok_or(ImageErr::OutOfBounds)?;
+
+        layer_to_rgb(layer, self.width(), self.height(), options)
+    }
+
+    /// ```
+    /// use aoc_2019::advent_image::Image;
+    /// use aoc_2019::advent_image::export::{ExportFormat, ExportOptions};
+    ///
+    /// let img = Image::new_from_ints(2, 1, &vec![2,1, 0,0]).unwrap();
+    /// let ppm = img.encode(ExportFormat::Ppm, &ExportOptions::new()).unwrap();
+    ///
+    /// assert!(ppm.starts_with(b"P6"));
+    /// assert!(ppm.ends_with(&[0, 0, 0, 255, 255, 255]));
+    /// ```
+    pub fn encode(&self, format: ExportFormat, options: &ExportOptions) -> Result<Vec<u8>, ImageErr> {
+        encode(self.to_rgb(options)?, format)
+    }
+
+    pub fn encode_layer(&self, index: usize, format: ExportFormat, options: &ExportOptions) -> Result<Vec<u8>, ImageErr> {
+        encode(self.layer_to_rgb(index, options)?, format)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ExportFormat, options: &ExportOptions) -> Result<(), ImageErr> {
+        save_bytes(&self.encode(format, options)?, path)
+    }
+
+    /// ```
+    /// use aoc_2019::advent_image::{Image, Pixel};
+    /// use aoc_2019::advent_image::export::{ExportFormat, ExportOptions};
+    ///
+    /// let img = Image::new_from_ints(2, 1, &vec![2,1, 0,0]).unwrap();
+    /// let options = ExportOptions::new().with_scale(3).with_color(Pixel::Transparent, [0, 0, 255]);
+    /// let path = std::env::temp_dir().join(format!("aoc_2019_layer_doctest_{}.png", std::process::id()));
+    ///
+    /// img.save_layer(0, &path, ExportFormat::Png, &options).unwrap();
+    /// let png = image::open(&path).unwrap().to_rgb();
+    /// std::fs::remove_file(&path).unwrap();
+    ///
+    /// assert_eq!(png.dimensions(), (6, 3));
+    /// assert_eq!(png.get_pixel(2, 2).0, [0, 0, 255]);
+    /// assert_eq!(png.get_pixel(3, 0).0, [255, 255, 255]);
+    /// ```
+    pub fn save_layer<P: AsRef<Path>>(&self, index: usize, path: P, format: ExportFormat, options: &ExportOptions) -> Result<(), ImageErr> {
+        save_bytes(&self.encode_layer(index, format, options)?, path)
+    }
+}
@@ -1,3 +1,6 @@
+pub mod export;
+pub mod encode;
+pub mod stream;
 
 #[derive(Debug, Fail)]
 pub enum ImageErr {
@@ -7,6 +10,28 @@ pub enum ImageErr {
     ParseError,
     #[fail(display = "Something was unexpectedly out of bounds")]
     OutOfBounds,
+    #[fail(display = "A palette can have at most 10 entries, found {}", _0)]
+    InvalidPalette(usize),
+    #[fail(display = "Custom compositing rules can't be used when streaming")]
+    CustomRuleInStream,
+    #[fail(display = "A {}x{} image can't be read at scale {}", width, height, scale)]
+    InvalidScale { width: u32, height: u32, scale: u32 },
+    #[fail(display = "Couldn't encode image: {}", _0)]
+    Encode(#[cause] image::ImageError),
+    #[fail(display = "Couldn't write image: {}", _0)]
+    Io(#[cause] std::io::Error),
+}
+
+impl From<image::ImageError> for ImageErr {
+    fn from(err: image::ImageError) -> ImageErr {
+        ImageErr::Encode(err)
+    }
+}
+
+impl From<std::io::Error> for ImageErr {
+    fn from(err: std::io::Error) -> ImageErr {
+        ImageErr::Io(err)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -14,6 +39,102 @@ pub enum Pixel {
     Black, // 0
     White, // 1
     Transparent, // 2
+    Other(u8), // 3-9, only valid with a custom palette
+}
+
+impl Pixel {
+    pub fn from_digit(digit: u32) -> Option<Pixel> {
+        match digit {
+            0 => Some(Pixel::Black),
+            1 => Some(Pixel::White),
+            2 => Some(Pixel::Transparent),
+            3..=9 => Some(Pixel::Other(digit as u8)),
+            _ => None,
+        }
+    }
+
+    pub fn digit(&self) -> u32 {
+        match *self {
+            Pixel::Black => 0,
+            Pixel::White => 1,
+            Pixel::Transparent => 2,
+            Pixel::Other(d) => d as u32,
+        }
+    }
+}
+
+/// How a pixel is drawn over the pixel in the layer below it
+#[derive(Debug, Clone, Copy)]
+pub enum Compositing {
+    /// Hides everything below
+    Opaque,
+    /// Shows whatever is below
+    Transparent,
+    /// Computes the result from the pixel and the one below, in that order
+    Custom(fn(Pixel, Pixel) -> Pixel),
+}
+
+/// The digits an image may contain and how each composites. Entry `i`
+/// describes digit `i`.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    rules: Vec<Compositing>,
+}
+
+impl Palette {
+    /// ```
+    /// use aoc_2019::advent_image::{Palette, Compositing, Image, Pixel, merge_layers_with};
+    ///
+    /// // 3 is transparent, and 2 draws as 1 over anything but 0
+    /// let palette = Palette::new(&[
+    ///     Compositing::Opaque,
+    ///     Compositing::Opaque,
+    ///     Compositing::Custom(|_top, below| if below == Pixel::Black { below } else { Pixel::White }),
+    ///     Compositing::Transparent,
+    /// ]).unwrap();
+    /// let img = Image::new_from_ints_with(3, 1, &vec![3,2,2, 0,0,1], &palette).unwrap();
+    /// let merged = merge_layers_with(&img, &palette).unwrap();
+    ///
+    /// assert_eq!(merged.layers()[0].pixels()[..], [Pixel::Black, Pixel::Black, Pixel::White][..]);
+    /// assert!(Image::new_from_ints_with(1, 1, &vec![4], &palette).is_err());
+    /// assert!(Palette::new(&[Compositing::Opaque; 11]).is_err());
+    /// ```
+    pub fn new(rules: &[Compositing]) -> Result<Palette, ImageErr> {
+        if rules.len() > 10 {
+            return Err(ImageErr::InvalidPalette(rules.len()));
+        }
+
+        Ok(Palette { rules: rules.to_vec() })
+    }
+
+    /// Black, white and transparent, as used by the day 8 images
+    pub fn standard() -> Palette {
+        Palette { rules: vec![Compositing::Opaque, Compositing::Opaque, Compositing::Transparent] }
+    }
+
+    pub fn len(&self) -> usize { self.rules.len() }
+
+    pub fn is_empty(&self) -> bool { self.rules.is_empty() }
+
+    pub fn rule(&self, pixel: Pixel) -> Option<Compositing> {
+        self.rules.get(pixel.digit() as usize).cloned()
+    }
+
+    pub fn pixel(&self, digit: u32) -> Result<Pixel, ImageErr> {
+        match Pixel::from_digit(digit) {
+            Some(p) if (digit as usize) < self.rules.len() => Ok(p),
+            _ => Err(ImageErr::ParseError),
+        }
+    }
+
+    /// Draws `top` over `below`
+    pub fn composite(&self, top: Pixel, below: Pixel) -> Pixel {
+        match self.rule(top) {
+            Some(Compositing::Transparent) => below,
+            Some(Compositing::Custom(f)) => f(top, below),
+            _ => top,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -22,46 +143,52 @@ pub struct Layer {
 }
 
 impl Layer {
-    pub fn new_from_ints(pixels: &Vec<u32>) -> Layer {
-        Layer { pixels: pixels.iter().map(|p| match *p {
-            0 => Pixel::Black,
-            1 => Pixel::White,
-            2 => Pixel::Transparent,
-            _ => panic!("Invalid pixel"),
-        }).collect() }
+    pub fn new_from_ints(pixels: &[u32]) -> Result<Layer, ImageErr> {
+        Layer::new_from_ints_with(pixels, &Palette::standard())
+    }
+
+    pub fn new_from_ints_with(pixels: &[u32], palette: &Palette) -> Result<Layer, ImageErr> {
+        Ok(Layer { pixels: pixels.iter().map(|p| palette.pixel(*p)).collect::<Result<Vec<Pixel>, ImageErr>>()? })
     }
 
-    pub fn new_from_pixels(pixels: &Vec<Pixel>) -> Layer {
-        Layer { pixels: pixels.clone() }
+    pub fn new_from_pixels(pixels: &[Pixel]) -> Layer {
+        Layer { pixels: pixels.to_vec() }
     }
 
     pub fn pixels(&self) -> &Vec<Pixel> { &self.pixels }
 
     pub fn pixel(&self, p: usize) -> Option<&Pixel> { self.pixels.get(p) }
 
-    pub fn get_zero_count(&self) -> u32 {
-        let mut zero_count = 0;
+    /// How often each digit occurs, indexed by digit
+    ///
+    /// ```
+    /// use aoc_2019::advent_image::{Layer, Pixel};
+    /// let layer = Layer::new_from_ints(&vec![0,1,1,2,1]).unwrap();
+    ///
+    /// assert_eq!(layer.histogram()[..3], [1, 3, 1]);
+    /// assert_eq!(layer.count(Pixel::White), 3);
+    /// ```
+    pub fn histogram(&self) -> [u32; 10] {
+        let mut histogram = [0; 10];
         for p in &self.pixels {
-            if let Pixel::Black = p {
-                zero_count += 1;
-            }
+            histogram[p.digit() as usize] += 1;
         }
 
-        zero_count
+        histogram
+    }
+
+    pub fn count(&self, pixel: Pixel) -> u32 {
+        self.histogram()[pixel.digit() as usize]
+    }
+
+    pub fn get_zero_count(&self) -> u32 {
+        self.count(Pixel::Black)
     }
 
     pub fn one_cnt_mul_two_cnt(&self) -> u32 {
-        let mut one_cnt = 0;
-        let mut two_cnt = 0;
-        for p in &self.pixels {
-            match *p {
-                Pixel::White => { one_cnt += 1; },
-                Pixel::Transparent => { two_cnt += 1 },
-                _ => {}
-            }
-        }
+        let histogram = self.histogram();
 
-        one_cnt * two_cnt
+        histogram[1] * histogram[2]
     }
 }
 
@@ -80,69 +207,81 @@ pub struct Image {
 /// assert_eq!(merged.layers().get(0).unwrap().pixels()[..], vec![Pixel::Black, Pixel::White, Pixel::White, Pixel::Black][..]);
 /// ```
 pub fn merge_layers(image: &Image) -> Result<Image, ImageErr> {
-    let mut output: Vec<Pixel> = vec![Pixel::Transparent; (image.width() * image.height()) as usize];
+    merge_layers_with(image, &Palette::standard())
+}
 
-    for l in image.layers().iter() {
-        for (p_i, p) in l.pixels().iter().enumerate() {
-            let existing = output.get(p_i).ok_or(ImageErr::OutOfBounds)?;
+/// Composites the layers from the bottom up using the palette's rules
+pub fn merge_layers_with(image: &Image, palette: &Palette) -> Result<Image, ImageErr> {
+    let count = (image.width() * image.height()) as usize;
+    let mut output: Vec<Pixel> = vec![Pixel::Transparent; count];
 
-            if let Pixel::Transparent = existing {
-                std::mem::replace(&mut output[p_i], *p);
-            }
+    for (i, l) in image.layers().iter().rev().enumerate() {
+        if l.pixels().len() != count {
+            return Err(ImageErr::OutOfBounds);
+        }
+
+        for (p_i, p) in l.pixels().iter().enumerate() {
+            output[p_i] = if i == 0 { *p } else { palette.composite(*p, output[p_i]) };
         }
     }
 
     Ok(Image::new_from_layers(
         image.width(),
         image.height(),
-        &vec![Layer::new_from_pixels(&output)]
+        &[Layer::new_from_pixels(&output)]
     ))
 }
 
 /// ```
-/// use aoc_2019::advent_image::{pixels_to_layers, Layer};
+/// use aoc_2019::advent_image::{pixels_to_layers, Layer, ImageErr};
 /// let layers = pixels_to_layers(3, 2, &vec![0,1,2,0,1,2,0,1,2,0,1,2]).unwrap();
 ///
-/// assert_eq!(layers[..], vec![Layer::new_from_ints(&vec![0,1,2,0,1,2]), Layer::new_from_ints(&vec![0,1,2,0,1,2])][..]);
+/// assert_eq!(layers[..], vec![Layer::new_from_ints(&vec![0,1,2,0,1,2]).unwrap(), Layer::new_from_ints(&vec![0,1,2,0,1,2]).unwrap()][..]);
+///
+/// match pixels_to_layers(3, 2, &vec![0,1,2,0,1,2,0]) {
+///     Err(ImageErr::InvalidPixelCount) => (),
+///     res => panic!("Expected an invalid pixel count, got {:?}", res),
+/// }
+/// match pixels_to_layers(3, 1, &vec![0,1,3]) {
+///     Err(ImageErr::ParseError) => (),
+///     res => panic!("Expected a parse error, got {:?}", res),
+/// }
 /// ```
-pub fn pixels_to_layers(width: u32, height: u32, pixels: &Vec<u32>) -> Result<Vec<Layer>, ImageErr> {
-    let count = (width * height) as usize;
-    let mut layers: Vec<Layer> = vec![];
-
-    for (i, _) in pixels.iter().step_by(count).enumerate() {
-        if (i + count) > pixels.len() {
-            return Err(ImageErr::InvalidPixelCount);
-        }
+pub fn pixels_to_layers(width: u32, height: u32, pixels: &[u32]) -> Result<Vec<Layer>, ImageErr> {
+    pixels_to_layers_with(width, height, pixels, &Palette::standard())
+}
 
-        let pixels_in_layer: Vec<u32> = pixels
-            .iter()
-            .skip(i * count)
-            .take(count)
-            .map(|p| *p)
-            .collect();
+pub fn pixels_to_layers_with(width: u32, height: u32, pixels: &[u32], palette: &Palette) -> Result<Vec<Layer>, ImageErr> {
+    let count = (width * height) as usize;
 
-        layers.push(
-            Layer::new_from_ints(&pixels_in_layer)
-        );
+    if count == 0 || pixels.len() % count != 0 {
+        return Err(ImageErr::InvalidPixelCount);
     }
 
-    Ok(layers)
+    pixels
+        .chunks(count)
+        .map(|pixels_in_layer| Layer::new_from_ints_with(pixels_in_layer, palette))
+        .collect()
 }
 
 impl Image {
-    pub fn new_from_ints(width: u32, height: u32, pixels: &Vec<u32>) -> Result<Image, ImageErr> {
+    pub fn new_from_ints(width: u32, height: u32, pixels: &[u32]) -> Result<Image, ImageErr> {
+        Image::new_from_ints_with(width, height, pixels, &Palette::standard())
+    }
+
+    pub fn new_from_ints_with(width: u32, height: u32, pixels: &[u32], palette: &Palette) -> Result<Image, ImageErr> {
         Ok(Image {
             width,
             height,
-            layers: pixels_to_layers(width, height, &pixels)?,
+            layers: pixels_to_layers_with(width, height, pixels, palette)?,
         })
     }
 
-    pub fn new_from_layers(width: u32, height: u32, layers: &Vec<Layer>) -> Image {
+    pub fn new_from_layers(width: u32, height: u32, layers: &[Layer]) -> Image {
         Image {
             width,
             height,
-            layers: layers.clone(),
+            layers: layers.to_vec(),
         }
     }
 
@@ -159,35 +298,64 @@ impl Image {
     }
 
     pub fn day08a_challenge(&self) -> Option<u32> {
-        let mut fewest_zeroes = 999;
-        let mut challenge: Option<u32> = None;
-        for layer in &self.layers {
-            let zero_count = layer.get_zero_count();
-
-            if zero_count < fewest_zeroes {
-                fewest_zeroes = zero_count;
-                challenge = Some(layer.one_cnt_mul_two_cnt());
-            }
-        }
+        self.min_by(|l| l.count(Pixel::Black)).map(|l| l.one_cnt_mul_two_cnt())
+    }
 
-        challenge
+    /// The first layer with the smallest key
+    ///
+    /// ```
+    /// use aoc_2019::advent_image::{Image, Pixel};
+    /// let img = Image::new_from_ints(2, 2, &vec![0,0,1,2, 0,1,1,1, 2,2,2,1]).unwrap();
+    ///
+    /// assert_eq!(img.min_by(|l| l.count(Pixel::Black)), img.layers().get(2));
+    /// assert_eq!(img.max_by(|l| l.count(Pixel::White)), img.layers().get(1));
+    /// ```
+    pub fn min_by<K: Ord, F: Fn(&Layer) -> K>(&self, key: F) -> Option<&Layer> {
+        self.layers.iter().min_by_key(|l| key(l))
     }
 
-    pub fn day08b_challenge(&self) -> Result<Vec<Vec<Pixel>>, ImageErr> {
-        let image = merge_layers(&self)?;
+    /// The last layer with the largest key
+    pub fn max_by<K: Ord, F: Fn(&Layer) -> K>(&self, key: F) -> Option<&Layer> {
+        self.layers.iter().max_by_key(|l| key(l))
+    }
+
+    /// ```
+    /// use aoc_2019::advent_image::{Image, Pixel};
+    /// let img = Image::new_from_ints(3, 2, &vec![0,1,2,1,1,0]).unwrap();
+    ///
+    /// assert_eq!(img.row(0, 1), Some(&[Pixel::White, Pixel::White, Pixel::Black][..]));
+    /// assert_eq!(img.column(0, 2), Some(vec![Pixel::Transparent, Pixel::Black]));
+    /// assert_eq!(img.pixel_at(0, 0, 1), Some(Pixel::White));
+    /// assert_eq!(img.pixel_at(0, 3, 0), None);
+    /// assert_eq!(img.row(1, 0), None);
+    /// ```
+    pub fn row(&self, layer: usize, y: u32) -> Option<&[Pixel]> {
+        if y >= self.height {
+            return None;
+        }
 
-        let layer = image.layers().get(0).ok_or(ImageErr::OutOfBounds)?;
+        let start = (y * self.width) as usize;
+        self.layers.get(layer)?.pixels().get(start..(start + self.width as usize))
+    }
 
-        let mut out: Vec<Vec<Pixel>> = vec![];
-        for y in 0..self.height {
-            let mut line: Vec<Pixel> = vec![];
-            for x in 0..self.width {
-                line.push(*layer.pixels().get((x + (y * self.width)) as usize).ok_or(ImageErr::OutOfBounds)?);
-            }
-            out.push(line);
+    pub fn column(&self, layer: usize, x: u32) -> Option<Vec<Pixel>> {
+        (0..self.height).map(|y| self.pixel_at(layer, x, y)).collect()
+    }
+
+    pub fn pixel_at(&self, layer: usize, x: u32, y: u32) -> Option<Pixel> {
+        if x >= self.width || y >= self.height {
+            return None;
         }
 
-        Ok(out)
+        self.layers.get(layer)?.pixel((x + y * self.width) as usize).cloned()
+    }
+
+    pub fn day08b_challenge(&self) -> Result<Vec<Vec<Pixel>>, ImageErr> {
+        let image = merge_layers(&self)?;
+
+        (0..self.height)
+            .map(|y| image.row(0, y).map(|r| r.to_vec()).ok_or(ImageErr::OutOfBounds))
+            .collect()
     }
 }
 
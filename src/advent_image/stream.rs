@@ -0,0 +1,201 @@
+use crate::advent_image::{Compositing, Image, ImageErr, Layer, Palette, Pixel};
+use std::io::{BufRead, BufReader, Read};
+
+/// Merges layers as their digits arrive, keeping only one merged layer in
+/// memory however many layers the input has. Layers are drawn top first, so
+/// a pixel is settled by the first opaque digit seen for it.
+#[derive(Debug, Clone)]
+pub struct StreamDecoder {
+    width: u32,
+    height: u32,
+    palette: Palette,
+    settled: Vec<Option<Pixel>>,
+    /// Most recent transparent pixel, shown where nothing is opaque
+    below: Vec<Pixel>,
+    pos: usize,
+    layers: usize,
+}
+
+impl StreamDecoder {
+    pub fn new(width: u32, height: u32) -> Result<StreamDecoder, ImageErr> {
+        StreamDecoder::with_palette(width, height, &Palette::standard())
+    }
+
+    /// Custom compositing rules need the pixels below them, which haven't
+    /// been read yet, so palettes using them are rejected
+    pub fn with_palette(width: u32, height: u32, palette: &Palette) -> Result<StreamDecoder, ImageErr> {
+        let count = (width * height) as usize;
+
+        if count == 0 {
+            return Err(ImageErr::InvalidPixelCount);
+        }
+        if (0..palette.len()).any(|d| matches!(Pixel::from_digit(d as u32).and_then(|p| palette.rule(p)), Some(Compositing::Custom(_)))) {
+            return Err(ImageErr::CustomRuleInStream);
+        }
+
+        Ok(StreamDecoder {
+            width,
+            height,
+            palette: palette.clone(),
+            settled: vec![None; count],
+            below: vec![Pixel::Transparent; count],
+            pos: 0,
+            layers: 0,
+        })
+    }
+
+    /// Number of layers read completely so far
+    pub fn layers_read(&self) -> usize { self.layers }
+
+    pub fn push_digit(&mut self, digit: u32) -> Result<(), ImageErr> {
+        let pixel = self.palette.pixel(digit)?;
+
+        if self.settled[self.pos].is_none() {
+            match self.palette.rule(pixel) {
+                Some(Compositing::Transparent) => self.below[self.pos] = pixel,
+                _ => self.settled[self.pos] = Some(pixel),
+            }
+        }
+
+        self.pos += 1;
+        if self.pos == self.settled.len() {
+            self.pos = 0;
+            self.layers += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Reads digits until the end of the input, skipping whitespace
+    pub fn read_from<R: Read>(&mut self, reader: R) -> Result<(), ImageErr> {
+        let mut reader = BufReader::new(reader);
+
+        loop {
+            let consumed = {
+                let buf = reader.fill_buf()?;
+                if buf.is_empty() {
+                    return Ok(());
+                }
+
+                for b in buf.iter().filter(|b| !b.is_ascii_whitespace()) {
+                    let digit = (*b as char).to_digit(10).ok_or(ImageErr::ParseError)?;
+                    self.push_digit(digit)?;
+                }
+
+                buf.len()
+            };
+
+            reader.consume(consumed);
+        }
+    }
+
+    /// The merged image, failing if the input stopped partway through a layer
+    pub fn finish(self) -> Result<Image, ImageErr> {
+        if self.pos != 0 {
+            return Err(ImageErr::InvalidPixelCount);
+        }
+
+        let pixels: Vec<Pixel> = self.settled.iter().zip(self.below.iter()).map(|(s, b)| s.unwrap_or(*b)).collect();
+
+        Ok(Image::new_from_layers(self.width, self.height, &[Layer::new_from_pixels(&pixels)]))
+    }
+}
+
+/// Decodes and merges an image from a stream of digits
+///
+/// ```
+/// use aoc_2019::advent_image::{merge_layers, Image};
+/// use aoc_2019::advent_image::stream::decode_reader;
+///
+/// let input = "0222112222120000\n";
+/// let merged = decode_reader(input.as_bytes(), 2, 2).unwrap();
+/// let ints: Vec<u32> = input.trim().chars().map(|c| c.to_digit(10).unwrap()).collect();
+///
+/// assert_eq!(merged, merge_layers(&Image::new_from_ints(2, 2, &ints).unwrap()).unwrap());
+/// assert!(decode_reader("02221".as_bytes(), 2, 2).is_err());
+/// assert!(decode_reader("0x22".as_bytes(), 2, 2).is_err());
+/// ```
+pub fn decode_reader<R: Read>(reader: R, width: u32, height: u32) -> Result<Image, ImageErr> {
+    decode_reader_with(reader, width, height, &Palette::standard())
+}
+
+pub fn decode_reader_with<R: Read>(reader: R, width: u32, height: u32, palette: &Palette) -> Result<Image, ImageErr> {
+    let mut decoder = StreamDecoder::with_palette(width, height, palette)?;
+    decoder.read_from(reader)?;
+
+    decoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advent_image::merge_layers;
+    use std::fs::File;
+    use std::io;
+
+    /// `layers` fully transparent layers followed by one checkerboard layer,
+    /// generated on the fly
+    struct Generated {
+        count: usize,
+        layers: usize,
+        pos: usize,
+    }
+
+    impl Read for Generated {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut n = 0;
+            let total = self.count * (self.layers + 1);
+
+            while n < buf.len() && self.pos < total {
+                let i = self.pos % self.count;
+                buf[n] = if self.pos / self.count < self.layers { b'2' } else if i % 2 == 0 { b'0' } else { b'1' };
+                n += 1;
+                self.pos += 1;
+            }
+
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_day8() {
+        let ints: Vec<u32> = std::fs::read_to_string("input/2019/day8.txt").unwrap()
+            .trim()
+            .chars()
+            .map(|c| c.to_digit(10).unwrap())
+            .collect();
+        let expected = merge_layers(&Image::new_from_ints(25, 6, &ints).unwrap()).unwrap();
+
+        let mut decoder = StreamDecoder::new(25, 6).unwrap();
+        decoder.read_from(File::open("input/2019/day8.txt").unwrap()).unwrap();
+
+        assert_eq!(decoder.layers_read(), ints.len() / 150);
+        assert_eq!(decoder.finish().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_many_layers() {
+        let reader = Generated { count: 6, layers: 100_000, pos: 0 };
+        let merged = decode_reader(reader, 3, 2).unwrap();
+
+        assert_eq!(merged.layers()[0].pixels()[..], [Pixel::Black, Pixel::White, Pixel::Black, Pixel::White, Pixel::Black, Pixel::White][..]);
+    }
+
+    #[test]
+    fn test_palettes() {
+        let custom = Palette::new(&[Compositing::Opaque, Compositing::Custom(|top, _| top)]).unwrap();
+        let transparent_three = Palette::new(&[
+            Compositing::Opaque,
+            Compositing::Opaque,
+            Compositing::Opaque,
+            Compositing::Transparent,
+        ]).unwrap();
+
+        match StreamDecoder::with_palette(1, 1, &custom) {
+            Err(ImageErr::CustomRuleInStream) => (),
+            res => panic!("Expected custom rules to be rejected, got {:?}", res),
+        }
+        assert_eq!(decode_reader_with("32 33".as_bytes(), 2, 1, &transparent_three).unwrap().layers()[0].pixels()[..], [Pixel::Other(3), Pixel::Transparent][..]);
+        assert!(StreamDecoder::new(0, 3).is_err());
+    }
+}
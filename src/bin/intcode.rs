@@ -0,0 +1,202 @@
+extern crate aoc_2019;
+
+use aoc_2019::intcode::{get_instruction, execute, InstrType};
+use aoc_2019::intcode::instructions::Program;
+use aoc_2019::intcode::loader::{load_file, load_str};
+use aoc_2019::intcode::ascii::{encode_line, render_output};
+use std::io::{self, BufRead, Write};
+use std::process;
+
+const USAGE: &str = "Usage: intcode [OPTIONS] <FILE> [INPUT]...
+
+Runs the Intcode program in FILE. INPUTs are fed to the program first, after
+which further input is read from stdin one line at a time.
+
+Options:
+    --ascii              Read input lines as text and print outputs < 128 as characters
+    --trace              Print every executed instruction to stderr
+    --max-steps <N>      Abort after executing N instructions
+    --dump-memory        Print the final memory to stderr when the program halts
+    -h, --help           Print this message";
+
+#[derive(Debug)]
+struct Options {
+    path: String,
+    inputs: Vec<String>,
+    ascii: bool,
+    trace: bool,
+    max_steps: Option<u64>,
+    dump_memory: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut path: Option<String> = None;
+    let mut inputs = vec![];
+    let mut ascii = false;
+    let mut trace = false;
+    let mut max_steps = None;
+    let mut dump_memory = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match &arg[..] {
+            "--ascii" => ascii = true,
+            "--trace" => trace = true,
+            "--dump-memory" => dump_memory = true,
+            "--max-steps" => {
+                let n = iter.next().ok_or("--max-steps expects a value")?;
+                max_steps = Some(n.parse::<u64>().map_err(|_| format!("Invalid --max-steps value: {}", n))?);
+            },
+            flag if flag.starts_with("--") => return Err(format!("Unknown option: {}", flag)),
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => inputs.push(arg.clone()),
+        }
+    }
+
+    Ok(Options {
+        path: path.ok_or("Missing FILE")?,
+        inputs,
+        ascii,
+        trace,
+        max_steps,
+        dump_memory,
+    })
+}
+
+fn line_to_inputs(line: &str, ascii: bool) -> Result<Vec<i64>, String> {
+    if ascii {
+        Ok(encode_line(line))
+    } else {
+        load_str(line).map_err(|e| e.to_string())
+    }
+}
+
+fn write_output(out: &mut dyn Write, val: i64, ascii: bool) -> io::Result<()> {
+    if ascii {
+        write!(out, "{}", render_output(&[val]))?;
+    } else {
+        writeln!(out, "{}", val)?;
+    }
+
+    out.flush()
+}
+
+fn dump_memory(program: &Program) -> String {
+    let ints = program.ints();
+    let len = ints.keys().max().map(|max| max + 1).unwrap_or(0);
+
+    (0..len)
+        .map(|i| program.get_int(i).to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn run(opts: &Options) -> Result<(), String> {
+    let ints = load_file(&opts.path).map_err(|e| e.to_string())?;
+
+    let mut initial_inputs = vec![];
+    for input in &opts.inputs {
+        initial_inputs.append(&mut line_to_inputs(input, opts.ascii)?);
+    }
+
+    let mut program = Program::new(ints, 0, vec![], initial_inputs, 0);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut steps: u64 = 0;
+
+    while !program.has_exited() {
+        if let Some(max) = opts.max_steps {
+            if steps >= max {
+                return Err(format!("Exceeded the maximum of {} steps", max));
+            }
+        }
+
+        let instruction = get_instruction(&program).map_err(|e| e.to_string())?;
+
+        if opts.trace {
+            eprintln!("[{:>8}] ptr={:<6} rel_base={:<6} {:?}", steps, program.pointer(), program.rel_base(), instruction);
+        }
+
+        if let InstrType::Input(_) = instruction {
+            while program.inputs().is_empty() {
+                let line = lines
+                    .next()
+                    .ok_or("Expected input, but stdin was closed")?
+                    .map_err(|e| e.to_string())?;
+
+                for val in line_to_inputs(&line, opts.ascii)? {
+                    program = program.push_input(val);
+                }
+            }
+        }
+
+        let output_cnt = program.output_count();
+        program = execute(&instruction, program).map_err(|e| e.to_string())?;
+        steps += 1;
+
+        for val in program.outputs_since(output_cnt) {
+            write_output(&mut out, *val, opts.ascii).map_err(|e| e.to_string())?;
+        }
+    }
+
+    if opts.dump_memory {
+        eprintln!("{}", dump_memory(&program));
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(msg) = run(&opts) {
+        eprintln!("Error: {}", msg);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let opts = parse_args(&args(&["--trace", "prog.txt", "1", "-5", "--max-steps", "100"])).unwrap();
+
+        assert_eq!(opts.path, "prog.txt");
+        assert_eq!(opts.inputs, vec!["1", "-5"]);
+        assert!(opts.trace);
+        assert!(!opts.ascii);
+        assert_eq!(opts.max_steps, Some(100));
+
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["--max-steps", "x", "prog.txt"])).is_err());
+        assert!(parse_args(&args(&["--bogus", "prog.txt"])).is_err());
+    }
+
+    #[test]
+    fn test_line_to_inputs() {
+        assert_eq!(line_to_inputs("1, 2,-3", false).unwrap(), vec![1, 2, -3]);
+        assert_eq!(line_to_inputs("NOT A J", true).unwrap(), vec![78, 79, 84, 32, 65, 32, 74, 10]);
+        assert!(line_to_inputs("a", false).is_err());
+    }
+}
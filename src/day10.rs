@@ -1,76 +1,120 @@
 use std::fmt;
 use failure::_core::fmt::{Formatter, Error};
-use std::collections::{HashSet, HashMap};
+use std::collections::{BTreeMap, HashSet, HashMap, VecDeque};
 use std::cmp::Ordering;
 
-#[derive(Debug, Clone, PartialEq, Copy)]
-pub struct CoordToOther {
-    x: f64,
-    y: f64,
-    radial: f64,
-    angular: i64,
+/// Direction from one grid point to another as an integer vector divided by
+/// the gcd of its components, so points on the same line of sight share it
+/// exactly. `dy` grows downwards, like the rows of the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Direction {
+    dx: i64,
+    dy: i64,
 }
 
-fn laser_hit(grouped_by_angle: &mut HashMap<i64, Vec<CoordToOther>>, angle: i64) -> Option<CoordToOther> {
-    let i_love_clones = grouped_by_angle.clone();
-    let targets = i_love_clones.get(&angle);
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
 
-    if targets.is_some() {
-        let v = targets.unwrap();
-        if let None = v.get(0) {
+impl Direction {
+    /// The direction from `from` to `to` and how many steps of it apart they
+    /// are, `None` for the same point
+    ///
+    /// ```
+    /// use aoc_2019::day10::Direction;
+    ///
+    /// let (dir, steps) = Direction::between((1, 1), (7, -3)).unwrap();
+    ///
+    /// assert_eq!((dir.dx(), dir.dy(), steps), (3, -2, 2));
+    /// assert_eq!(Direction::between((2, 2), (2, 2)), None);
+    /// ```
+    pub fn between(from: (i64, i64), to: (i64, i64)) -> Option<(Direction, i64)> {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let steps = gcd(dx, dy);
+
+        if steps == 0 {
             return None;
         }
 
-        let first = v.get(0).unwrap();
+        Some((Direction { dx: dx / steps, dy: dy / steps }, steps))
+    }
 
-        let mut new_v: Vec<CoordToOther> = vec![];
-        for c in v.iter().skip(1) {
-            new_v.push(*c);
-        }
+    pub fn dx(&self) -> i64 { self.dx }
 
-        grouped_by_angle.insert(angle, new_v);
-        return Some(*first);
-    }
+    pub fn dy(&self) -> i64 { self.dy }
 
-    return None;
+    /// Quarter turn the direction falls in, clockwise from straight up, each
+    /// including its starting edge
+    fn quadrant(&self) -> u8 {
+        match (self.dx, self.dy) {
+            (dx, dy) if dx >= 0 && dy < 0 => 0,
+            (dx, dy) if dx > 0 && dy >= 0 => 1,
+            (dx, dy) if dx <= 0 && dy > 0 => 2,
+            _ => 3,
+        }
+    }
 }
 
-pub fn sort_coords_by_laser_hit(coords: &Vec<CoordToOther>) -> Option<Vec<CoordToOther>> {
-    let mut all_angles = HashSet::new();
-    for &coord in coords {
-        all_angles.insert(coord.angular);
+impl Ord for Direction {
+    /// Clockwise order starting from straight up, compared without any
+    /// floating point angles
+    ///
+    /// ```
+    /// use aoc_2019::day10::Direction;
+    ///
+    /// let dir = |x, y| Direction::between((0, 0), (x, y)).unwrap().0;
+    /// let mut dirs = vec![dir(-1, -1), dir(0, 3), dir(1, 0), dir(0, -1), dir(1000, -1), dir(1001, -1), dir(-1, 0)];
+    /// dirs.sort();
+    ///
+    /// assert_eq!(dirs, vec![dir(0, -1), dir(1000, -1), dir(1001, -1), dir(1, 0), dir(0, 1), dir(-1, 0), dir(-1, -1)]);
+    /// ```
+    fn cmp(&self, other: &Direction) -> Ordering {
+        // Within a quadrant `other` is clockwise of `self` when the cross
+        // product is positive, with y pointing down
+        let cross = self.dx * other.dy - self.dy * other.dx;
+
+        self.quadrant().cmp(&other.quadrant()).then(0.cmp(&cross))
     }
+}
 
-    let mut grouped_by_angle = HashMap::new();
-    for angle in &all_angles {
-        let mut coords_for_angle: Vec<CoordToOther> = coords.clone()
-            .iter()
-            .filter(|c| c.angular == *angle)
-            .map(|c| *c)
-            .collect();
+impl PartialOrd for Direction {
+    fn partial_cmp(&self, other: &Direction) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        coords_for_angle.sort_by(|a, b| {
-            a.radial.partial_cmp(&b.radial).unwrap_or(Ordering::Equal)
-        });
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub struct CoordToOther {
+    x: f64,
+    y: f64,
+    /// Multiples of `direction` between the pole and this asteroid
+    steps: i64,
+    direction: Direction,
+}
 
-        grouped_by_angle.insert(*angle, coords_for_angle);
+/// Orders asteroids in the order a laser rotating clockwise from straight up
+/// hits them, only the nearest one in a direction being hit each turn
+pub fn sort_coords_by_laser_hit(coords: &Vec<CoordToOther>) -> Option<Vec<CoordToOther>> {
+    let mut grouped_by_direction: BTreeMap<Direction, Vec<CoordToOther>> = BTreeMap::new();
+    for coord in coords {
+        grouped_by_direction.entry(coord.direction).or_default().push(*coord);
     }
 
-    let mut hits: Vec<CoordToOther> = vec![];
+    let mut queues: Vec<VecDeque<CoordToOther>> = grouped_by_direction
+        .into_values()
+        .map(|mut group| {
+            group.sort_by_key(|c| c.steps);
+            group.into_iter().collect()
+        })
+        .collect();
 
-    laser_hit(&mut grouped_by_angle, 0)
-        .map(|c| {
-            hits.push(c);
-//            println!("{}/{}", hits.len(), coords.len());
-        });
+    let mut hits: Vec<CoordToOther> = vec![];
 
     while hits.len() < coords.len() {
-        for i in (0..36000).rev() {
-            laser_hit(&mut grouped_by_angle, i)
-                .map(|c| {
-                    hits.push(c);
-//                    println!("{}/{}", hits.len(), coords.len());
-                });
+        for queue in queues.iter_mut() {
+            if let Some(c) = queue.pop_front() {
+                hits.push(c);
+            }
         }
     }
 
@@ -133,7 +177,7 @@ impl Map {
     }
 
     pub fn get_loc(&self, x: usize, y: usize) -> Option<Location> {
-        self.locations.get((self.width * y) + x).map(|loc| *loc)
+        self.locations.get((self.width * y) + x).copied()
     }
 
     pub fn locations(&self) -> Vec<Location> {
@@ -141,22 +185,7 @@ impl Map {
     }
 
     pub fn lookup_angles(&self, x: i64, y: i64) -> Option<Vec<CoordToOther>> {
-        self.angle_lookup.get(&(x, y))
-            .map(|v| {
-                v.iter().map(|c| {
-                    let mut norm = (c.angular - 18000) % 36000;
-                    if norm < 0 {
-                        norm = 36000 + norm;
-                    }
-
-                    CoordToOther {
-                        x: c.x,
-                        y: c.y,
-                        radial: c.radial,
-                        angular: norm,
-                    }
-                }).collect()
-            })
+        self.angle_lookup.get(&(x, y)).cloned()
     }
 
     pub fn process(&self) -> Map {
@@ -165,36 +194,31 @@ impl Map {
         let new_locations = self.locations.iter()
             .enumerate()
             .map(|(p_i, pole)| {
-                let pole_coord = ((p_i % self.width) as f64, (p_i / self.width) as f64);
+                let pole_coord = ((p_i % self.width) as i64, (p_i / self.width) as i64);
 
                 match pole {
                     Location::Asteroid(None) => {
-                        let mut others: HashSet<i64> = HashSet::new();
+                        let mut others: HashSet<Direction> = HashSet::new();
                         let mut coords: Vec<CoordToOther> = vec![];
 
                         for (a_i, &ast) in self.locations.iter().enumerate() {
-                            if a_i == p_i {
-                                continue;
-                            }
-
                             if let Location::Asteroid(_) = ast {
-                                let ast_coord = ((a_i % self.width) as f64, (a_i / self.width) as f64);
-
-                                let (length, angle) = calc_polar_coord(pole_coord, ast_coord);
-
-                                let key = (angle * 100.0) as i64;
-                                others.insert(key);
-
-                                coords.push(CoordToOther {
-                                    x: ast_coord.0,
-                                    y: ast_coord.1,
-                                    radial: length,
-                                    angular: key,
-                                });
+                                let ast_coord = ((a_i % self.width) as i64, (a_i / self.width) as i64);
+
+                                if let Some((direction, steps)) = Direction::between(pole_coord, ast_coord) {
+                                    others.insert(direction);
+
+                                    coords.push(CoordToOther {
+                                        x: ast_coord.0 as f64,
+                                        y: ast_coord.1 as f64,
+                                        steps,
+                                        direction,
+                                    });
+                                }
                             }
                         }
 
-                        angle_lookup.insert((pole_coord.0 as i64, pole_coord.1 as i64), coords);
+                        angle_lookup.insert(pole_coord, coords);
 
                         Location::Asteroid(Some(others.len()))
                     },
@@ -231,9 +255,7 @@ impl Map {
         let (x, y, _score) = self.get_highest_score()?;
         let coords = self.lookup_angles(x, y)?;
 
-        let ordered_hits = sort_coords_by_laser_hit(&coords);
-
-        ordered_hits
+        sort_coords_by_laser_hit(&coords)
     }
 }
 
@@ -301,13 +323,12 @@ pub fn solve_part2(input: &Map) -> i64 {
 
     let two_hundredth = hits.get(199).unwrap();
 
-    return ((two_hundredth.x * 100.0) + two_hundredth.y) as i64
+    ((two_hundredth.x * 100.0) + two_hundredth.y) as i64
 }
 
 #[cfg(test)]
 mod tests {
     use crate::day10::*;
-//    use std::collections::HashMap;
 
     #[test]
     fn test_process_small() {
@@ -398,40 +419,73 @@ mod tests {
 
         assert_eq!(map.get_highest_score().unwrap(), (11, 13, 210));
 
-//        Slow AF unoptimized
-//        let hits = map.lasers().unwrap();
-//
-//        let hit_1 = hits.get(0).unwrap();
-//        assert_eq!((hit_1.x as u64, hit_1.y as u64), (11, 12));
-//
-//        let hit_2 = hits.get(1).unwrap();
-//        assert_eq!((hit_2.x as u64, hit_2.y as u64), (12, 1));
-//
-//        let hit_3 = hits.get(2).unwrap();
-//        assert_eq!((hit_3.x as u64, hit_3.y as u64), (12, 2));
-//
-//        let hit_10 = hits.get(9).unwrap();
-//        assert_eq!((hit_10.x as u64, hit_10.y as u64), (12, 8));
-//
-//        let hit_20 = hits.get(19).unwrap();
-//        assert_eq!((hit_20.x as u64, hit_20.y as u64), (16, 0));
-//
-//        let hit_50 = hits.get(49).unwrap();
-//        assert_eq!((hit_50.x as u64, hit_50.y as u64), (16, 9));
-//
-//        let hit_100 = hits.get(99).unwrap();
-//        assert_eq!((hit_100.x as u64, hit_100.y as u64), (10, 16));
-//
-//        let hit_199 = hits.get(198).unwrap();
-//        assert_eq!((hit_199.x as u64, hit_199.y as u64), (9, 6));
-//
-//        let hit_200 = hits.get(199).unwrap();
-//        assert_eq!((hit_200.x as u64, hit_200.y as u64), (8, 2));
-//
-//        let hit_201 = hits.get(200).unwrap();
-//        assert_eq!((hit_201.x as u64, hit_201.y as u64), (10, 9));
-//
-//        let hit_299 = hits.get(298).unwrap();
-//        assert_eq!((hit_299.x as u64, hit_299.y as u64), (11, 1));
+        let hits = map.lasers().unwrap();
+
+        let hit_1 = hits.first().unwrap();
+        assert_eq!((hit_1.x as u64, hit_1.y as u64), (11, 12));
+
+        let hit_2 = hits.get(1).unwrap();
+        assert_eq!((hit_2.x as u64, hit_2.y as u64), (12, 1));
+
+        let hit_3 = hits.get(2).unwrap();
+        assert_eq!((hit_3.x as u64, hit_3.y as u64), (12, 2));
+
+        let hit_10 = hits.get(9).unwrap();
+        assert_eq!((hit_10.x as u64, hit_10.y as u64), (12, 8));
+
+        let hit_20 = hits.get(19).unwrap();
+        assert_eq!((hit_20.x as u64, hit_20.y as u64), (16, 0));
+
+        let hit_50 = hits.get(49).unwrap();
+        assert_eq!((hit_50.x as u64, hit_50.y as u64), (16, 9));
+
+        let hit_100 = hits.get(99).unwrap();
+        assert_eq!((hit_100.x as u64, hit_100.y as u64), (10, 16));
+
+        let hit_199 = hits.get(198).unwrap();
+        assert_eq!((hit_199.x as u64, hit_199.y as u64), (9, 6));
+
+        let hit_200 = hits.get(199).unwrap();
+        assert_eq!((hit_200.x as u64, hit_200.y as u64), (8, 2));
+
+        let hit_201 = hits.get(200).unwrap();
+        assert_eq!((hit_201.x as u64, hit_201.y as u64), (10, 9));
+
+        let hit_299 = hits.get(298).unwrap();
+        assert_eq!((hit_299.x as u64, hit_299.y as u64), (11, 1));
+    }
+
+    #[test]
+    fn test_large_grid_directions() {
+        // (1000, 1) and (1001, 1) are less than a hundredth of a degree
+        // apart as seen from the corner, but still separate lines of sight
+        let mut input = String::from("#");
+        input.push_str(&".".repeat(1001));
+        input.push('\n');
+        input.push_str(&".".repeat(1000));
+        input.push_str("##");
+
+        let map = input_generator(&input).process();
+
+        assert_eq!(map.get_loc(0, 0), Some(Location::Asteroid(Some(2))));
+        assert_eq!(map.get_loc(1000, 1), Some(Location::Asteroid(Some(2))));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_laser_order_small() {
+        let input = ".#....#####...#..\n\
+                           ##...##.#####..##\n\
+                           ##...#...#.#####.\n\
+                           ..#.....#...###..\n\
+                           ..#.#.....#....##";
+        let coords = input_generator(input).process().lookup_angles(8, 3).unwrap();
+        let hits: Vec<(i64, i64)> = sort_coords_by_laser_hit(&coords).unwrap()
+            .iter()
+            .map(|c| (c.x as i64, c.y as i64))
+            .collect();
+
+        assert_eq!(hits[..9], [(8, 1), (9, 0), (9, 1), (10, 0), (9, 2), (11, 1), (12, 1), (11, 2), (15, 1)][..]);
+        assert_eq!(hits.len(), coords.len());
+        assert_eq!(hits.last(), Some(&(14, 3)));
+    }
+}
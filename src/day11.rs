@@ -1,7 +1,9 @@
 use std::collections::HashMap;
-use intcode::{get_instruction, InstrType};
-use intcode::instructions::{ProgramErr, Program, Instruction};
+use intcode::agent::{Agent, AgentErr, drive_recorded};
+use intcode::instructions::Program;
 use failure::_core::fmt::{Formatter, Error};
+use intcode::loader::load_str;
+use intcode::replay::ReplayLog;
 
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum Color {
@@ -16,15 +18,15 @@ fn color_to_int(c: Color) -> i64 {
     }
 }
 
-fn int_to_color(i: i64) -> Color {
+fn int_to_color(i: i64) -> Option<Color> {
     match i {
-        0 => Color::Black,
-        1 => Color::White,
-        _ => panic!("Invalid color"),
+        0 => Some(Color::Black),
+        1 => Some(Color::White),
+        _ => None,
     }
 }
 
-type Coords = (i64, i64);
+pub type Coords = (i64, i64);
 
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum Direction {
@@ -34,22 +36,37 @@ pub enum Direction {
     Left,
 }
 
+impl Direction {
+    pub fn all() -> [Direction; 4] {
+        [Direction::Up, Direction::Right, Direction::Down, Direction::Left]
+    }
+
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Copy)]
-enum Angle {
+pub enum Angle {
     Left,
     Right,
 }
 
-fn int_to_angle(i: i64) -> Angle {
+fn int_to_angle(i: i64) -> Option<Angle> {
     match i {
-        0 => Angle::Left,
-        1 => Angle::Right,
-        _ => panic!("Invalid angle state"),
+        0 => Some(Angle::Left),
+        1 => Some(Angle::Right),
+        _ => None,
     }
 }
 
-fn int_to_direction(prev: Direction, i: i64) -> Direction {
-    match (prev, int_to_angle(i)) {
+fn turn(prev: Direction, angle: Angle) -> Direction {
+    match (prev, angle) {
         (Direction::Up, Angle::Left) => Direction::Left,
         (Direction::Up, Angle::Right) => Direction::Right,
         (Direction::Right, Angle::Left) => Direction::Up,
@@ -61,7 +78,7 @@ fn int_to_direction(prev: Direction, i: i64) -> Direction {
     }
 }
 
-fn move_robot(current: Coords, dir: Direction) -> Coords {
+pub fn move_robot(current: Coords, dir: Direction) -> Coords {
     match dir {
         Direction::Up => (current.0, current.1 + 1),
         Direction::Right => (current.0 + 1, current.1),
@@ -164,73 +181,55 @@ impl std::fmt::Display for Ship {
     }
 }
 
-pub fn run(program_ints: &Vec<i64>, start: Color) -> Result<Ship, ProgramErr> {
-    let mut ship = Ship::new(&HashMap::new(), Direction::Up, (0, 0));
-
-    let mut program = Program::new(
-        program_ints.clone(),
-        0,
-        vec![],
-        vec![color_to_int(start)],
-        0
-    );
-
-    let mut col_dir: (Option<i64>, Option<i64>) = (None, None);
-
-    loop {
-        let instruction = get_instruction(&program)?;
-
-        program = match instruction {
-            InstrType::Add(instr) => instr.run(program)?,
-            InstrType::Mul(instr) => instr.run(program)?,
-            InstrType::Input(instr) => instr.run(program)?,
-            InstrType::Output(instr) => {
-                let mut next_program = instr.run(program)?;
-                let out = next_program.outputs();
-                let last = out.last().expect("Expected output missing");
-
-                col_dir = match col_dir {
-                    (None, None) => (Some(*last), None),
-                    (Some(c), None) => (Some(c), Some(*last)),
-                    (Some(_c), Some(_d)) => (Some(*last), None),
-                    _ => panic!("Invalid state"),
-                };
-
-                if let (Some(c), Some(d)) = col_dir {
-                    ship = ship.paint_panel_and_set_direction(
-                        int_to_color(c),
-                        int_to_direction(ship.get_robot_dir(), d)
-                    );
-
-                    next_program = next_program.push_input(
-                        color_to_int(
-                            ship.get_color(ship.robot_pos)
-                        )
-                    );
-                }
-
-                next_program
-            },
-            InstrType::JmpIfFalse(instr) => instr.run(program)?,
-            InstrType::JmpIfTrue(instr) => instr.run(program)?,
-            InstrType::LessThan(instr) => instr.run(program)?,
-            InstrType::Equals(instr) => instr.run(program)?,
-            InstrType::Exit(instr) => instr.run(program)?,
-            InstrType::RelBaseOffset(instr) => instr.run(program)?,
-        };
-
-        if program.has_exited() {
-            return Ok(ship)
-        }
+/// Paints the panel below the robot, then turns and moves one panel forward
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub struct Paint {
+    pub color: Color,
+    pub angle: Angle,
+}
+
+/// The robot reads the starting panel's color first, and the camera after that
+struct PaintingRobot {
+    ship: Ship,
+    start: Option<Color>,
+}
+
+impl Agent for PaintingRobot {
+    type Command = Paint;
+
+    fn arity(&self) -> usize { 2 }
+
+    fn decode(&self, outputs: &[i64]) -> Option<Paint> {
+        Some(Paint { color: int_to_color(outputs[0])?, angle: int_to_angle(outputs[1])? })
+    }
+
+    fn handle(&mut self, command: Paint) {
+        let dir = turn(self.ship.get_robot_dir(), command.angle);
+        self.ship = self.ship.paint_panel_and_set_direction(command.color, dir);
+    }
+
+    fn sense(&mut self) -> Option<i64> {
+        let color = self.start.take().unwrap_or_else(|| self.ship.get_color(self.ship.get_robot_pos()));
+
+        Some(color_to_int(color))
     }
 }
 
+pub fn run(program_ints: &Vec<i64>, start: Color) -> Result<Ship, AgentErr> {
+    run_recorded(program_ints, start, &mut ReplayLog::new())
+}
+
+/// Like `run`, but records every camera reading the robot is fed into `log`
+pub fn run_recorded(program_ints: &Vec<i64>, start: Color, log: &mut ReplayLog) -> Result<Ship, AgentErr> {
+    let mut robot = PaintingRobot { ship: Ship::new(&HashMap::new(), Direction::Up, (0, 0)), start: Some(start) };
+    drive_recorded(Program::new(program_ints.clone(), 0, vec![], vec![], 0), &mut robot, log)?;
+
+    Ok(robot.ship)
+}
+
 #[aoc_generator(day11)]
 pub fn input_generator(input: &str) -> Vec<i64> {
-    input.split(',')
-        .map(|s| s.parse::<i64>())
-        .filter_map(Result::ok)
-        .collect()
+    load_str(input).expect("Invalid Intcode program")
 }
 
 #[aoc(day11, part1)]
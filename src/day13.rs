@@ -1,11 +1,13 @@
-use intcode::instructions::{Program, ProgramErr, Instruction};
-use intcode::{InstrType, get_instruction};
+use intcode::instructions::Program;
+use intcode::agent::{Agent, AgentErr, drive_recorded};
 use std::collections::HashMap;
 use std::fmt;
 use failure::_core::fmt::{Formatter, Error};
 use std::io::{Write, stdout};
 use termion::cursor;
 use termion::raw::IntoRawMode;
+use intcode::loader::load_str;
+use intcode::replay::ReplayLog;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Block {
@@ -122,55 +124,92 @@ impl fmt::Display for Screen {
     }
 }
 
-pub fn run(program_ints: &Vec<i64>) -> Result<Screen, ProgramErr> {
-    let mut program = Program::new(program_ints.clone(), 0, vec![], vec![], 0);
-    let mut screen = Screen::new();
-    let mut block_instr: (Option<usize>, Option<usize>, Option<Block>) = (None, None, None);
-
-    loop {
-        program = match get_instruction(&program)? {
-            InstrType::Add(instr) => instr.run(program)?,
-            InstrType::Mul(instr) => instr.run(program)?,
-            InstrType::Input(instr) => instr.run(program)?,
-            InstrType::Output(instr) => {
-                let next = instr.run(program)?;
-                let output = *next.outputs().last().expect("No last");
-
-                block_instr = match block_instr {
-                    (None, None, None) => (Some(output as usize), None, None),
-                    (Some(_), Some(_), Some(_)) => (Some(output as usize), None, None),
-                    (Some(x), None, None) => (Some(x), Some(output as usize), None),
-                    (Some(x), Some(y), None) => (Some(x), Some(y), Some(int_to_block(output).expect("Invalid block"))),
-                    _ => panic!("Invalid state"),
-                };
-
-                if let (Some(x), Some(y), Some(b)) = block_instr {
-                    screen = screen.set_block(x, y, b);
-//                    print_screen_with_termion(&screen);
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArcadeCommand {
+    Draw { x: usize, y: usize, block: Block },
+    Score(i64),
+}
+
+/// The cabinet draws every output triple, and moves the joystick towards the
+/// ball whenever the game asks for input
+struct Arcade {
+    screen: Screen,
+    score: i64,
+    ball_x: usize,
+    paddle_x: usize,
+}
+
+impl Agent for Arcade {
+    type Command = ArcadeCommand;
+
+    fn arity(&self) -> usize { 3 }
+
+    fn decode(&self, outputs: &[i64]) -> Option<ArcadeCommand> {
+        match outputs {
+            [-1, 0, score] => Some(ArcadeCommand::Score(*score)),
+            [x, y, block] if *x >= 0 && *y >= 0 => {
+                Some(ArcadeCommand::Draw { x: *x as usize, y: *y as usize, block: int_to_block(*block)? })
+            },
+            _ => None,
+        }
+    }
+
+    fn handle(&mut self, command: ArcadeCommand) {
+        match command {
+            ArcadeCommand::Score(score) => self.score = score,
+            ArcadeCommand::Draw { x, y, block } => {
+                match block {
+                    Block::Ball => self.ball_x = x,
+                    Block::Paddle => self.paddle_x = x,
+                    _ => (),
                 }
 
-                next
+                self.screen = self.screen.set_block(x, y, block);
+//                print_screen_with_termion(&self.screen);
             },
-            InstrType::JmpIfFalse(instr) => instr.run(program)?,
-            InstrType::JmpIfTrue(instr) => instr.run(program)?,
-            InstrType::LessThan(instr) => instr.run(program)?,
-            InstrType::Equals(instr) => instr.run(program)?,
-            InstrType::Exit(instr) => instr.run(program)?,
-            InstrType::RelBaseOffset(instr) => instr.run(program)?,
-        };
-
-        if program.has_exited() {
-            return Ok(screen)
         }
     }
+
+    fn sense(&mut self) -> Option<i64> {
+        Some((self.ball_x as i64 - self.paddle_x as i64).signum())
+    }
+}
+
+pub fn run(program_ints: &Vec<i64>) -> Result<Screen, AgentErr> {
+    run_recorded(program_ints, &mut ReplayLog::new())
+}
+
+fn run_arcade(program_ints: &[i64], log: &mut ReplayLog) -> Result<Arcade, AgentErr> {
+    let mut arcade = Arcade { screen: Screen::new(), score: 0, ball_x: 0, paddle_x: 0 };
+    drive_recorded(Program::new(program_ints.to_vec(), 0, vec![], vec![], 0), &mut arcade, log)?;
+
+    Ok(arcade)
+}
+
+/// Like `run`, but records every joystick input into `log`. Without quarters
+/// the game never reads the joystick, so this only records with a patched
+/// program; `play_recorded` inserts them itself.
+pub fn run_recorded(program_ints: &Vec<i64>, log: &mut ReplayLog) -> Result<Screen, AgentErr> {
+    Ok(run_arcade(program_ints, log)?.screen)
+}
+
+/// Inserts quarters and plays until the game ends, returning the last score
+pub fn play(program_ints: &[i64]) -> Result<i64, AgentErr> {
+    play_recorded(program_ints, &mut ReplayLog::new())
+}
+
+/// Like `play`, but records every joystick input into `log`. Replaying it
+/// needs the quarters inserted as well, i.e. `[0]` set to 2.
+pub fn play_recorded(program_ints: &[i64], log: &mut ReplayLog) -> Result<i64, AgentErr> {
+    let mut free_play = program_ints.to_vec();
+    *free_play.first_mut().ok_or(AgentErr::EmptyProgram)? = 2;
+
+    Ok(run_arcade(&free_play, log)?.score)
 }
 
 #[aoc_generator(day13)]
 pub fn input_generator(input: &str) -> Vec<i64> {
-    input.split(',')
-        .map(|s| s.parse::<i64>())
-        .filter_map(Result::ok)
-        .collect()
+    load_str(input).expect("Invalid Intcode program")
 }
 
 #[aoc(day13, part1)]
@@ -178,4 +217,58 @@ pub fn solve_part1(input: &[i64]) -> usize {
     let screen = run(&input.to_vec()).unwrap();
 
     screen.num_of(Block::Block)
-}
\ No newline at end of file
+}
+
+#[aoc(day13, part2)]
+pub fn solve_part2(input: &[i64]) -> i64 {
+    play(input).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::replay::replay;
+    use std::fs;
+
+    #[test]
+    fn test_play_keeps_last_score() {
+        // Halts straight away unless the quarters in [0] turn the first
+        // instruction into a multiplication, then draws a ball and reports
+        // two scores
+        let ints = vec![
+            1, 0, 0, 33,
+            1008, 33, 4, 34,
+            1005, 34, 14,
+            99, 0, 0,
+            104, 1, 104, 1, 104, 4,
+            104, -1, 104, 0, 104, 10,
+            104, -1, 104, 0, 104, 1234,
+            99, 0, 0,
+        ];
+
+        assert_eq!(play(&ints).unwrap(), 1234);
+    }
+
+    #[test]
+    fn test_replay_free_play() {
+        let ints = input_generator(&fs::read_to_string("input/2019/day13.txt").unwrap());
+        let mut log = ReplayLog::new();
+        let score = play_recorded(&ints, &mut log).unwrap();
+
+        let mut free_play = ints.clone();
+        free_play[0] = 2;
+        let outputs = replay(&free_play, &log).unwrap().outputs();
+        let replayed = outputs.chunks(3).rev().find(|c| c[..2] == [-1, 0]).map(|c| c[2]);
+
+        assert!(!log.is_empty());
+        assert_eq!(replayed, Some(score));
+    }
+
+    #[test]
+    fn test_play_empty() {
+        match play(&[]) {
+            Err(AgentErr::EmptyProgram) => (),
+            res => panic!("Expected an empty program error, got {:?}", res),
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use intcode::run_program;
-use intcode::instructions::Program;
+use intcode::instructions::{Program, ProgramErr};
+use intcode::search::{find_patch, run_patched};
+use intcode::symbolic::analyze;
+use intcode::loader::load_str;
 
 #[aoc_generator(day2)]
 pub fn input_generator(input: &str) -> Vec<i64> {
-    input.split(',')
-        .map(|s| s.parse::<i64>())
-        .filter_map(Result::ok)
-        .collect()
+    load_str(input).expect("Invalid Intcode program")
 }
 
 #[aoc(day2, part1)]
@@ -16,22 +16,62 @@ pub fn solve_part1(input: &[i64]) -> i64 {
     std::mem::replace(&mut mut_input[1], 12);
     std::mem::replace(&mut mut_input[2], 2);
 
-    let res = run_program(&mut_input, &vec![]).unwrap();
+    let res = run_program(&mut_input, &[]).unwrap();
 
     *res.ints().get(&0).unwrap()
 }
 
-pub fn replace_and_process(program: &Program, noun: i64, verb: i64) -> i64 {
-    let nouned = program.set_ints(1, noun);
-    let verbed = nouned.set_ints(2, verb);
+pub fn replace_and_process(program: &Program, noun: i64, verb: i64) -> Result<i64, ProgramErr> {
+    let res = run_patched(program, &[(1, noun), (2, verb)])?;
 
-    let res = run_program(&verbed.as_vec(0, 1000), &vec![]).unwrap();
+    Ok(res.get_int(0))
+}
+
+pub fn find_noun_verb(program: &Program, target: i64) -> Option<(i64, i64)> {
+    find_patch(program, &[1, 2], 0..=99, 0, target, 100_000)
+        .map(|found| (found[0], found[1]))
+}
+
+/// Expresses [0] as a polynomial over the noun and verb, then solves it for
+/// `target` one noun at a time. Nouns where that can't be done exactly, e.g.
+/// because the polynomial overflows, are skipped.
+pub fn solve_noun_verb(program_ints: &[i64], target: i64) -> Option<(i64, i64)> {
+    let res = analyze(program_ints, &[(1, "noun"), (2, "verb")], &[], 100_000).ok()?;
+    let expr = res.get(0)?;
+
+    for noun in 0..=99 {
+        let by_verb = match expr.substitute("noun", noun) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        if by_verb.degree_in("verb") > 1 {
+            let found = (0..=99).find(|verb| {
+                by_verb.substitute("verb", *verb).and_then(|p| p.as_constant()) == Some(target)
+            });
+
+            if let Some(verb) = found {
+                return Some((noun, verb));
+            }
+            continue;
+        }
 
+        let a = by_verb.coefficient(&["verb"]);
+        let b = match by_verb.substitute("verb", 0).and_then(|p| p.as_constant()) {
+            Some(b) => b,
+            None => continue,
+        };
 
-    *res
-        .as_vec(0, 1)
-        .first()
-        .unwrap()
+        if a == 0 {
+            if b == target {
+                return Some((noun, 0));
+            }
+        } else if (target - b) % a == 0 && (0..=99).contains(&((target - b) / a)) {
+            return Some((noun, (target - b) / a));
+        }
+    }
+
+    None
 }
 
 #[aoc(day2, part2)]
@@ -44,14 +84,54 @@ pub fn solve_part2(input: &[i64]) -> i64 {
         0
     );
 
-    for noun in 0..98 {
-        for verb in 0..98 {
-            let val = replace_and_process(&program, noun, verb);
-            if val == 19690720 {
-                return 100 * noun + verb;
-            }
+    let (noun, verb) = solve_noun_verb(input, 19690720)
+        .or_else(|| find_noun_verb(&program, 19690720))
+        .expect("Couldn't reach 19690720");
+
+    100 * noun + verb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn brute_force(program_ints: &[i64], target: i64) -> Option<(i64, i64)> {
+        let program = Program::new(program_ints.to_vec(), 0, vec![], vec![], 0);
+
+        (0..=99)
+            .flat_map(|noun| (0..=99).map(move |verb| (noun, verb)))
+            .find(|(noun, verb)| replace_and_process(&program, *noun, *verb).ok() == Some(target))
+    }
+
+    #[test]
+    fn test_solve_noun_verb_day2() {
+        let ints = input_generator(&fs::read_to_string("input/2019/day2.txt").unwrap());
+
+        for target in &[19690720, solve_part1(&ints)] {
+            assert_eq!(solve_noun_verb(&ints, *target), brute_force(&ints, *target));
         }
     }
 
-    panic!("Couldn't reach 19690720");
+    #[test]
+    fn test_find_noun_verb() {
+        let ints = input_generator(&fs::read_to_string("input/2019/day2.txt").unwrap());
+        let program = Program::new(ints.clone(), 0, vec![], vec![], 0);
+        let toy = Program::new(vec![1101, 0, 0, 9, 1002, 9, 7, 0, 99, 0], 0, vec![], vec![], 0);
+
+        assert_eq!(find_noun_verb(&program, 19690720), brute_force(&ints, 19690720));
+        assert_eq!(find_noun_verb(&toy, 301), Some((0, 43)));
+        assert_eq!(find_noun_verb(&toy, 300), None);
+    }
+
+    #[test]
+    fn test_solve_noun_verb_linear() {
+        // [0] = 7 * (noun + verb)
+        let ints = vec![1101, 0, 0, 9, 1002, 9, 7, 0, 99, 0];
+
+        assert_eq!(solve_noun_verb(&ints, 301), Some((0, 43)));
+        assert_eq!(solve_noun_verb(&ints, 301), brute_force(&ints, 301));
+        assert_eq!(solve_noun_verb(&ints, 300), None);
+        assert_eq!(brute_force(&ints, 300), None);
+    }
 }
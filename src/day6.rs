@@ -1,4 +1,5 @@
 use failure::Error;
+use crate::orbit_map::OrbitGraph;
 
 #[derive(Debug, Fail, PartialEq)]
 pub enum OrbitErr {
@@ -509,29 +510,15 @@ pub fn find_root(nodes: &Vec<Node>) -> Option<usize> {
 }
 
 #[aoc(day6, part1)]
-pub fn solve_part1(input: &[Orbit]) -> u64 {
-    let orbits = input.to_vec();
-    let nodes = orbits_to_nodes(&orbits).unwrap();
+pub fn solve_part1(input: &[Orbit]) -> usize {
+    let graph = OrbitGraph::from_orbits(input).unwrap();
 
-    calc_total_orbits(&nodes).unwrap()
+    graph.total_orbits()
 }
 
 #[aoc(day6, part2)]
 pub fn solve_part2(input: &[Orbit]) -> usize {
-    let orbits = input.to_vec();
-    let nodes = orbits_to_nodes(&orbits).unwrap();
+    let graph = OrbitGraph::from_orbits(input).unwrap();
 
-    let from = nodes.iter().position(|n| n.name == "YOU").unwrap();
-    let end = nodes.iter().position(|n| n.name == "SAN").unwrap();
-    let root = find_root(&nodes).unwrap();
-
-    let root_to_end = traverse_children_until(&nodes, root, end).unwrap().unwrap();
-    let root_to_start = traverse_children_until(&nodes, root, from).unwrap().unwrap();
-
-    let diff_path1 = path_diff(&root_to_start, &root_to_end, PathDiffFavor::First).expect("first failed");
-    let diff_path2 = path_diff(&root_to_start, &root_to_end, PathDiffFavor::Second).expect("second failed");
-    let joined = join_diff_paths(&diff_path1, &diff_path2).unwrap();
-    let trimmed = trim_ends(&joined);
-
-    trimmed.len() - 1
+    graph.transfers("YOU", "SAN").expect("YOU and SAN aren't connected")
 }
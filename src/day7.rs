@@ -2,6 +2,7 @@ use intcode::run_program;
 use crate::intcode::instructions::{ProgramErr};
 use std::collections::HashMap;
 use failure::Error;
+use intcode::loader::load_str;
 
 pub fn get_best_run(map: &HashMap<String, i64>) -> i64 {
     let mut best = 0;
@@ -50,19 +51,19 @@ pub fn get_best_run(map: &HashMap<String, i64>) -> i64 {
 /// ```
 pub fn run_program_with_phase_settings(program: &Vec<i64>, initial_value: i64, a: i64, b: i64, c: i64, d: i64, e: i64) -> Result<i64, Error> {
     // A
-    let a_res = run_program(&program, &vec![a, initial_value])?;
+    let a_res = run_program(&program, &[a, initial_value])?;
     let a_output = *a_res.outputs().first().ok_or(ProgramErr::Missing { i: 0 })?;
     // B
-    let b_res = run_program(&program, &vec![b, a_output])?;
+    let b_res = run_program(&program, &[b, a_output])?;
     let b_output = *b_res.outputs().first().ok_or(ProgramErr::Missing { i: 0 })?;
     // C
-    let c_res = run_program(&program, &vec![c, b_output])?;
+    let c_res = run_program(&program, &[c, b_output])?;
     let c_output = *c_res.outputs().first().ok_or(ProgramErr::Missing { i: 0 })?;
     // D
-    let d_res = run_program(&program, &vec![d, c_output])?;
+    let d_res = run_program(&program, &[d, c_output])?;
     let d_output = *d_res.outputs().first().ok_or(ProgramErr::Missing { i: 0 })?;
     // E
-    let e_res = run_program(&program, &vec![e, d_output])?;
+    let e_res = run_program(&program, &[e, d_output])?;
 
     Ok(*e_res.outputs().first().ok_or(ProgramErr::Missing { i: 0 })?)
 }
@@ -91,10 +92,7 @@ pub fn run_program_without_feedback(program: &Vec<i64>) -> Result<HashMap<String
 
 #[aoc_generator(day7)]
 pub fn input_generator(input: &str) -> Vec<i64> {
-    input.split(',')
-        .map(|s| s.parse::<i64>())
-        .filter_map(Result::ok)
-        .collect()
+    load_str(input).expect("Invalid Intcode program")
 }
 
 #[aoc(day7, part1)]
@@ -11,14 +11,14 @@ pub fn input_generator(input: &str) -> Vec<u32> {
 
 #[aoc(day8, part1)]
 pub fn solve_part1(input: &[u32]) -> u32 {
-    let image = Image::new_from_ints(25, 6, &input.to_vec()).unwrap();
+    let image = Image::new_from_ints(25, 6, input).unwrap();
 
     image.day08a_challenge().ok_or(ImageErr::ParseError).unwrap()
 }
 
 #[aoc(day8, part2)]
 pub fn solve_part2(input: &[u32]) -> String {
-    let image = Image::new_from_ints(25, 6, &input.to_vec()).unwrap();
+    let image = Image::new_from_ints(25, 6, input).unwrap();
 
     let res = image.day08b_challenge().unwrap();
     let mut out = String::with_capacity(25 * 6 + 1);
@@ -36,6 +36,9 @@ pub fn solve_part2(input: &[u32]) -> String {
                 crate::advent_image::Pixel::White => {
                     out.push('#');
                 },
+                crate::advent_image::Pixel::Other(d) => {
+                    out.push(std::char::from_digit(*d as u32, 10).unwrap_or('?'));
+                },
             };
         }
     }
@@ -1,11 +1,9 @@
 use intcode::run_program;
+use intcode::loader::load_str;
 
 #[aoc_generator(day9)]
 pub fn input_generator(input: &str) -> Vec<i64> {
-    input.split(',')
-        .map(|s| s.parse::<i64>())
-        .filter_map(Result::ok)
-        .collect()
+    load_str(input).expect("Invalid Intcode program")
 }
 
 #[aoc(day9, part1)]
@@ -16,7 +14,7 @@ pub fn solve_part1(input: &[i64]) -> i64 {
         program.push(0);
     }
 
-    let res = run_program(&program, &vec![1]).unwrap();
+    let res = run_program(&program, &[1]).unwrap();
 
     *res.outputs().last().unwrap()
 }
@@ -29,7 +27,7 @@ pub fn solve_part2(input: &[i64]) -> i64 {
         program.push(0);
     }
 
-    let res = run_program(&program, &vec![2]).unwrap();
+    let res = run_program(&program, &[2]).unwrap();
 
     *res.outputs().last().unwrap()
 }
\ No newline at end of file
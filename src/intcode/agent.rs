@@ -0,0 +1,160 @@
+use intcode::run_until_blocked;
+use intcode::instructions::{Program, ProgramErr};
+use intcode::replay::ReplayLog;
+
+#[derive(Debug, Fail)]
+pub enum AgentErr {
+    #[fail(display = "{}", _0)]
+    Program(#[cause] ProgramErr),
+    #[fail(display = "Couldn't decode a command from {:?}", outputs)]
+    InvalidCommand { outputs: Vec<i64> },
+    #[fail(display = "Program is waiting for input but the agent has none")]
+    NoInput,
+    #[fail(display = "Agent commands have to be made of at least one output")]
+    ZeroArity,
+    #[fail(display = "The program is empty")]
+    EmptyProgram,
+}
+
+impl From<ProgramErr> for AgentErr {
+    fn from(err: ProgramErr) -> AgentErr {
+        AgentErr::Program(err)
+    }
+}
+
+/// Something an Intcode program controls. Every `arity` outputs are decoded
+/// into a command, and whenever the program reads, the agent senses its
+/// surroundings to supply the input.
+pub trait Agent {
+    type Command;
+
+    /// Number of outputs that make up one command
+    fn arity(&self) -> usize;
+
+    /// Returns `None` if the outputs don't form a valid command
+    fn decode(&self, outputs: &[i64]) -> Option<Self::Command>;
+
+    fn handle(&mut self, command: Self::Command);
+
+    /// The next input, or `None` if the agent has nothing to say
+    fn sense(&mut self) -> Option<i64>;
+}
+
+/// Runs the program until it halts, feeding its commands to the agent
+///
+/// ```
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::agent::{Agent, drive};
+///
+/// // Reads a number and outputs it together with its double, until it reads 0
+/// struct Doubler { pairs: Vec<(i64, i64)>, next: i64 }
+///
+/// impl Agent for Doubler {
+///     type Command = (i64, i64);
+///
+///     fn arity(&self) -> usize { 2 }
+///     fn decode(&self, outputs: &[i64]) -> Option<(i64, i64)> { Some((outputs[0], outputs[1])) }
+///     fn handle(&mut self, command: (i64, i64)) { self.pairs.push(command) }
+///     fn sense(&mut self) -> Option<i64> {
+///         self.next -= 1;
+///         Some(self.next)
+///     }
+/// }
+///
+/// let program = Program::new(vec![3, 100, 4, 100, 102, 2, 100, 101, 4, 101, 1005, 100, 0, 99], 0, vec![], vec![], 0);
+/// let mut agent = Doubler { pairs: vec![], next: 3 };
+///
+/// drive(program, &mut agent).unwrap();
+/// assert_eq!(agent.pairs, vec![(2, 4), (1, 2), (0, 0)]);
+/// ```
+pub fn drive<A: Agent>(program: Program, agent: &mut A) -> Result<Program, AgentErr> {
+    drive_recorded(program, agent, &mut ReplayLog::new())
+}
+
+/// Like `drive`, but records every input the agent supplies into `log`
+pub fn drive_recorded<A: Agent>(program: Program, agent: &mut A, log: &mut ReplayLog) -> Result<Program, AgentErr> {
+    let arity = agent.arity();
+    if arity == 0 {
+        return Err(AgentErr::ZeroArity);
+    }
+
+    let mut program = program;
+    let mut decoded = program.output_count();
+
+    loop {
+        program = run_until_blocked(program)?;
+
+        for chunk in program.outputs_since(decoded).chunks_exact(arity) {
+            let command = agent.decode(chunk).ok_or_else(|| AgentErr::InvalidCommand { outputs: chunk.to_vec() })?;
+
+            agent.handle(command);
+            decoded += arity;
+        }
+
+        if program.has_exited() {
+            return Ok(program);
+        }
+
+        program = program.push_input(agent.sense().ok_or(AgentErr::NoInput)?);
+        log.record_input(&program);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Collector {
+        commands: Vec<Vec<i64>>,
+        arity: usize,
+    }
+
+    impl Agent for Collector {
+        type Command = Vec<i64>;
+
+        fn arity(&self) -> usize { self.arity }
+
+        fn decode(&self, outputs: &[i64]) -> Option<Vec<i64>> {
+            if outputs.contains(&-1) { None } else { Some(outputs.to_vec()) }
+        }
+
+        fn handle(&mut self, command: Vec<i64>) { self.commands.push(command) }
+
+        fn sense(&mut self) -> Option<i64> { None }
+    }
+
+    fn collect(ints: Vec<i64>, arity: usize) -> (Result<Program, AgentErr>, Vec<Vec<i64>>) {
+        let mut agent = Collector { commands: vec![], arity };
+        let res = drive(Program::new(ints, 0, vec![], vec![], 0), &mut agent);
+
+        (res, agent.commands)
+    }
+
+    #[test]
+    fn test_arity() {
+        let (res, commands) = collect(vec![104, 1, 104, 2, 104, 3, 99], 2);
+
+        assert_eq!(commands, vec![vec![1, 2]]);
+        assert_eq!(res.unwrap().outputs(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_errors() {
+        match collect(vec![104, 1, 104, -1, 99], 2).0 {
+            Err(AgentErr::InvalidCommand { outputs }) => assert_eq!(outputs, vec![1, -1]),
+            res => panic!("Expected an invalid command, got {:?}", res),
+        }
+        match collect(vec![104, 1, 3, 0, 99], 1) {
+            (Err(AgentErr::NoInput), commands) => assert_eq!(commands, vec![vec![1]]),
+            res => panic!("Expected missing input, got {:?}", res),
+        }
+        match collect(vec![104, 1, 98], 1).0 {
+            Err(AgentErr::Program(_)) => (),
+            res => panic!("Expected a program error, got {:?}", res),
+        }
+        match collect(vec![104, 1, 99], 0).0 {
+            Err(AgentErr::ZeroArity) => (),
+            res => panic!("Expected a zero arity error, got {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use intcode::{run_until_blocked, awaits_input};
+use intcode::instructions::{Program, ProgramErr};
+use std::io::{BufRead, Write};
+use failure::Error;
+
+/// Turns a line of text into input codes, terminated by a newline
+///
+/// ```
+/// use aoc_2019::intcode::ascii::encode_line;
+///
+/// assert_eq!(encode_line("WALK"), vec![87, 65, 76, 75, 10]);
+/// ```
+pub fn encode_line(line: &str) -> Vec<i64> {
+    line.chars()
+        .map(|c| c as i64)
+        .chain(std::iter::once(10))
+        .collect()
+}
+
+/// Renders outputs below 128 as characters and passes larger values through as numbers
+///
+/// ```
+/// use aoc_2019::intcode::ascii::render_output;
+///
+/// assert_eq!(render_output(&[79, 75, 10, 19358416]), "OK\n19358416");
+/// ```
+pub fn render_output(outputs: &[i64]) -> String {
+    let mut out = String::new();
+
+    for val in outputs {
+        if (0..128).contains(val) {
+            out.push(*val as u8 as char);
+        } else {
+            out.push_str(&val.to_string());
+        }
+    }
+
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciiConsole {
+    program: Program,
+    rendered: usize,
+}
+
+impl AsciiConsole {
+    pub fn new(program_ints: &[i64]) -> AsciiConsole {
+        AsciiConsole {
+            program: Program::new(program_ints.to_vec(), 0, vec![], vec![], 0),
+            rendered: 0,
+        }
+    }
+
+    pub fn program(&self) -> &Program { &self.program }
+
+    pub fn has_exited(&self) -> bool { self.program.has_exited() }
+
+    pub fn awaits_input(&self) -> Result<bool, ProgramErr> { awaits_input(&self.program) }
+
+    /// Runs until the program halts or wants more input, returning the text it printed meanwhile
+    pub fn run(&mut self) -> Result<String, ProgramErr> {
+        self.program = run_until_blocked(std::mem::take(&mut self.program))?;
+
+        let text = render_output(self.program.outputs_since(self.rendered));
+        self.rendered = self.program.output_count();
+
+        Ok(text)
+    }
+
+    pub fn send_line(&mut self, line: &str) {
+        for val in encode_line(line) {
+            self.program = std::mem::take(&mut self.program).push_input(val);
+        }
+    }
+}
+
+/// Feeds the script one line at a time whenever the program asks for input and
+/// returns everything it printed
+///
+/// ```
+/// use aoc_2019::intcode::ascii::run_scripted;
+///
+/// // Echoes one line, then prints 1000
+/// let echo = vec![3,100,4,100,1008,100,10,101,1006,101,0,104,1000,99];
+///
+/// assert_eq!(run_scripted(&echo, &["hi"]).unwrap(), "hi\n1000");
+/// assert!(run_scripted(&echo, &[]).is_err());
+/// ```
+pub fn run_scripted(program_ints: &[i64], script: &[&str]) -> Result<String, ProgramErr> {
+    let mut console = AsciiConsole::new(program_ints);
+    let mut lines = script.iter();
+    let mut transcript = console.run()?;
+
+    while !console.has_exited() {
+        let line = lines.next().ok_or(ProgramErr::ExpectedInput)?;
+        console.send_line(line);
+        transcript.push_str(&console.run()?);
+    }
+
+    Ok(transcript)
+}
+
+/// Like `run_scripted`, but reads lines from `input` and writes output as it
+/// appears, e.g. for stdin/stdout
+pub fn run_interactive<R: BufRead, W: Write>(program_ints: &[i64], input: R, mut output: W) -> Result<Program, Error> {
+    let mut console = AsciiConsole::new(program_ints);
+    let mut lines = input.lines();
+
+    loop {
+        write!(output, "{}", console.run()?)?;
+        output.flush()?;
+
+        if console.has_exited() {
+            return Ok(console.program().clone());
+        }
+
+        let line = lines.next().ok_or(ProgramErr::ExpectedInput)??;
+        console.send_line(&line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_output() {
+        assert_eq!(render_output(&[]), "");
+        assert_eq!(render_output(&[72, 105]), "Hi");
+        assert_eq!(render_output(&[128, -1]), "128-1");
+    }
+
+    #[test]
+    fn test_console() {
+        let mut console = AsciiConsole::new(&[104, 62, 3, 100, 4, 100, 99]);
+
+        assert_eq!(console.run().unwrap(), ">");
+        assert!(console.awaits_input().unwrap());
+
+        console.send_line("x");
+        assert_eq!(console.run().unwrap(), "x");
+        assert!(console.has_exited());
+        assert_eq!(console.program().inputs(), vec![10]);
+    }
+
+    #[test]
+    fn test_run_interactive() {
+        let echo = vec![3, 100, 4, 100, 1008, 100, 10, 101, 1006, 101, 0, 104, 1000, 99];
+        let mut out: Vec<u8> = vec![];
+
+        run_interactive(&echo, "ok\n".as_bytes(), &mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "ok\n1000");
+    }
+}
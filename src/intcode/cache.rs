@@ -0,0 +1,150 @@
+use intcode::{get_instruction, execute, InstrType};
+use intcode::instructions::{Program, ProgramErr, Mode, parse_opcode};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+struct Entry {
+    instruction: InstrType,
+    size: i64,
+    /// The parameter an instruction writes to, if any
+    write: Option<(Mode, i64)>,
+}
+
+/// Decoded instructions keyed by address. Entries covering an address are
+/// dropped when `step_cached` writes to it, so self-modifying code still
+/// sees its own changes.
+#[derive(Debug, Default)]
+pub struct DecodeCache {
+    entries: HashMap<i64, Entry>,
+    hits: usize,
+    misses: usize,
+}
+
+fn decode(program: &Program) -> Result<Entry, ProgramErr> {
+    let instruction = get_instruction(program)?;
+    let addr = program.get_pointer();
+    let size = instruction.size();
+
+    let write = match instruction {
+        InstrType::Add(_) | InstrType::Mul(_) | InstrType::LessThan(_) | InstrType::Equals(_) => {
+            Some((parse_opcode(program.get_int(addr))?.c, program.get_int(addr + 3)))
+        },
+        InstrType::Input(_) => Some((parse_opcode(program.get_int(addr))?.a, program.get_int(addr + 1))),
+        _ => None,
+    };
+
+    Ok(Entry { instruction, size, write })
+}
+
+impl DecodeCache {
+    pub fn new() -> DecodeCache {
+        DecodeCache::default()
+    }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn hits(&self) -> usize { self.hits }
+
+    pub fn misses(&self) -> usize { self.misses }
+
+    /// Drops every cached instruction that covers `addr`
+    pub fn invalidate(&mut self, addr: i64) {
+        for start in (addr - 3)..=addr {
+            let covers = self.entries.get(&start).map(|e| start + e.size > addr).unwrap_or(false);
+
+            if covers {
+                self.entries.remove(&start);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Executes one instruction like `step`, decoding it through the cache. Writes
+/// made to the program outside of this function aren't seen by the cache, so
+/// `invalidate` or `clear` it after patching memory by hand.
+pub fn step_cached(program: Program, cache: &mut DecodeCache) -> Result<Program, ProgramErr> {
+    let pointer = program.get_pointer();
+
+    if cache.entries.contains_key(&pointer) {
+        cache.hits += 1;
+    } else {
+        cache.misses += 1;
+        let entry = decode(&program)?;
+        cache.entries.insert(pointer, entry);
+    }
+
+    let entry = &cache.entries[&pointer];
+    let written = match entry.write {
+        Some((Mode::Parameter, addr)) => Some(addr),
+        Some((Mode::Relative, offset)) => Some(program.rel_base() + offset),
+        _ => None,
+    };
+
+    let next = execute(&entry.instruction, program)?;
+
+    if let Some(addr) = written {
+        cache.invalidate(addr);
+    }
+
+    Ok(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::run_program;
+
+    fn run_cached(ints: Vec<i64>, inputs: Vec<i64>) -> (Program, DecodeCache) {
+        let mut program = Program::new(ints, 0, vec![], inputs, 0);
+        let mut cache = DecodeCache::new();
+
+        while !program.has_exited() {
+            program = step_cached(program, &mut cache).unwrap();
+        }
+
+        (program, cache)
+    }
+
+    #[test]
+    fn test_hits() {
+        // Counts [13] down from 3, outputting each value
+        let ints = vec![4, 13, 1001, 13, -1, 13, 1005, 13, 0, 99, 0, 0, 0, 3];
+        let (program, cache) = run_cached(ints.clone(), vec![]);
+
+        assert_eq!(program.outputs(), vec![3, 2, 1]);
+        assert_eq!(program, run_program(&ints, &[]).unwrap());
+        assert_eq!(cache.misses(), 4);
+        assert_eq!(cache.hits(), 6);
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // Doubles [20], then turns the add at 0 into a mul and loops while [20] < 10
+        let ints = vec![1, 20, 20, 20, 1101, 0, 2, 0, 1007, 20, 10, 21, 1005, 21, 0, 99, 0, 0, 0, 0, 3, 0];
+        let (program, _) = run_cached(ints.clone(), vec![]);
+
+        assert_eq!(program.get_int(20), 36);
+        assert_eq!(program, run_program(&ints, &[]).unwrap());
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let (_, mut cache) = run_cached(vec![1101, 1, 2, 7, 104, 0, 99, 0], vec![]);
+
+        assert_eq!(cache.len(), 3);
+        cache.invalidate(5);
+        assert_eq!(cache.len(), 2);
+        cache.invalidate(7);
+        assert_eq!(cache.len(), 2);
+        cache.invalidate(0);
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
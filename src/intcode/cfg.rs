@@ -0,0 +1,390 @@
+use intcode::instructions::{Program, Mode};
+use intcode::disasm::{disassemble, Disassembled};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Fallthrough,
+    Jump,
+    Call,
+    /// From a calling block to the return address it pushed before the call
+    CallReturn,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub from: i64,
+    pub to: i64,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start: i64,
+    pub instructions: Vec<Disassembled>,
+    /// Ends in a jump whose target isn't an immediate, e.g. a return through the stack
+    pub indirect: bool,
+}
+
+/// A region entered through a block that opens a stack frame with `rbo`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub entry: i64,
+    pub frame_size: i64,
+    pub blocks: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg {
+    blocks: BTreeMap<i64, BasicBlock>,
+    edges: Vec<Edge>,
+    functions: Vec<Function>,
+}
+
+/// Where control can go after an instruction, as far as can be told statically
+#[derive(Debug, Clone, PartialEq)]
+struct Flow {
+    fallthrough: Option<i64>,
+    jump: Option<i64>,
+    indirect: bool,
+}
+
+fn immediate(param: &(Mode, i64)) -> Option<i64> {
+    match param.0 {
+        Mode::Immediate => Some(param.1),
+        _ => None,
+    }
+}
+
+fn is_jump(instr: &Disassembled) -> bool {
+    instr.opcode == Some(5) || instr.opcode == Some(6)
+}
+
+fn flow(instr: &Disassembled) -> Flow {
+    let next = instr.addr + instr.size;
+
+    match instr.opcode {
+        Some(5) | Some(6) => {
+            let cond = immediate(&instr.params[0]).map(|v| (v != 0) == (instr.opcode == Some(5)));
+            let target = immediate(&instr.params[1]);
+
+            Flow {
+                fallthrough: if cond == Some(true) { None } else { Some(next) },
+                jump: if cond == Some(false) { None } else { target },
+                indirect: cond != Some(false) && target.is_none(),
+            }
+        },
+        Some(99) | None => Flow { fallthrough: None, jump: None, indirect: false },
+        _ => Flow { fallthrough: Some(next), jump: None, indirect: false },
+    }
+}
+
+/// The value an `add`/`mul` with only immediate operands stores on the stack,
+/// which right before a jump is usually a return address
+fn pushed_constant(instr: &Disassembled) -> Option<i64> {
+    if instr.params.len() != 3 || instr.params[2].0 != Mode::Relative {
+        return None;
+    }
+
+    let left = immediate(&instr.params[0])?;
+    let right = immediate(&instr.params[1])?;
+
+    match instr.opcode {
+        Some(1) => Some(left + right),
+        Some(2) => Some(left * right),
+        _ => None,
+    }
+}
+
+fn frame_size(instr: &Disassembled) -> Option<i64> {
+    match (instr.opcode, instr.params.first().and_then(immediate)) {
+        (Some(9), Some(size)) if size > 0 => Some(size),
+        _ => None,
+    }
+}
+
+/// Follows every statically known path from address 0, splits the reached
+/// instructions into basic blocks and groups those into functions
+///
+/// ```
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::cfg::extract_cfg;
+///
+/// // in [0]; jf [0], 7; out 1; hlt
+/// let cfg = extract_cfg(&Program::new(vec![3, 0, 1006, 0, 7, 104, 1, 99], 0, vec![], vec![], 0));
+///
+/// assert_eq!(cfg.blocks().keys().cloned().collect::<Vec<i64>>(), vec![0, 5, 7]);
+/// assert_eq!(cfg.successors(0), vec![5, 7]);
+/// ```
+pub fn extract_cfg(program: &Program) -> Cfg {
+    let mut instructions: BTreeMap<i64, Disassembled> = BTreeMap::new();
+    let mut leaders: BTreeSet<i64> = BTreeSet::new();
+    let mut call_returns: Vec<(i64, i64)> = vec![];
+    let mut queue: VecDeque<i64> = VecDeque::new();
+
+    leaders.insert(0);
+    queue.push_back(0);
+
+    while let Some(start) = queue.pop_front() {
+        let mut addr = start;
+        let mut pushed: Option<i64> = None;
+
+        while !instructions.contains_key(&addr) {
+            let instr = disassemble(program, addr);
+            if instr.is_data() {
+                break;
+            }
+
+            let f = flow(&instr);
+            pushed = pushed_constant(&instr).or(pushed);
+
+            if let Some(target) = f.jump {
+                leaders.insert(target);
+                queue.push_back(target);
+
+                if let (None, Some(ret)) = (f.fallthrough, pushed) {
+                    call_returns.push((instr.addr, ret));
+                    leaders.insert(ret);
+                    queue.push_back(ret);
+                }
+            }
+            if is_jump(&instr) {
+                if let Some(next) = f.fallthrough {
+                    leaders.insert(next);
+                }
+
+                // A push only pairs with the jump ending its own block
+                pushed = None;
+            }
+
+            instructions.insert(addr, instr);
+
+            match f.fallthrough {
+                Some(next) => addr = next,
+                None => break,
+            }
+        }
+    }
+
+    let mut blocks: BTreeMap<i64, BasicBlock> = BTreeMap::new();
+    let mut block_of: BTreeMap<i64, i64> = BTreeMap::new();
+    let mut current: Option<i64> = None;
+
+    for (addr, instr) in &instructions {
+        let prev_end = current
+            .and_then(|start| blocks.get(&start))
+            .and_then(|b| b.instructions.last())
+            .map(|i| (i.addr + i.size, flow(i).fallthrough.is_some() && !is_jump(i)));
+
+        let continues = match prev_end {
+            Some((end, falls)) => end == *addr && falls && !leaders.contains(addr),
+            None => false,
+        };
+
+        if !continues {
+            current = Some(*addr);
+            blocks.insert(*addr, BasicBlock { start: *addr, instructions: vec![], indirect: false });
+        }
+
+        let start = current.unwrap_or(*addr);
+        let block = blocks.get_mut(&start).expect("Block was just inserted");
+        block.indirect = flow(instr).indirect;
+        block.instructions.push(instr.clone());
+        block_of.insert(*addr, start);
+    }
+
+    let entries: HashSet<i64> = blocks
+        .values()
+        .filter(|b| b.instructions.first().and_then(frame_size).is_some())
+        .map(|b| b.start)
+        .collect();
+
+    let mut edges = vec![];
+    for block in blocks.values() {
+        let last = block.instructions.last().expect("Blocks are never empty");
+        let f = flow(last);
+
+        if let Some(next) = f.fallthrough {
+            if blocks.contains_key(&next) {
+                edges.push(Edge { from: block.start, to: next, kind: EdgeKind::Fallthrough });
+            }
+        }
+
+        if let Some(target) = f.jump.filter(|t| blocks.contains_key(t)) {
+            let is_call = entries.contains(&target) && target != block.start;
+            let kind = if is_call { EdgeKind::Call } else { EdgeKind::Jump };
+            edges.push(Edge { from: block.start, to: target, kind });
+        }
+
+        for (_, ret) in call_returns.iter().filter(|(from, _)| *from == last.addr) {
+            if blocks.contains_key(ret) {
+                edges.push(Edge { from: block.start, to: *ret, kind: EdgeKind::CallReturn });
+            }
+        }
+    }
+
+    let mut functions: Vec<Function> = vec![];
+    let mut sorted_entries: Vec<&i64> = entries.iter().collect();
+    sorted_entries.sort();
+
+    for entry in sorted_entries {
+        let mut seen: BTreeSet<i64> = BTreeSet::new();
+        let mut queue: VecDeque<i64> = VecDeque::new();
+        queue.push_back(*entry);
+
+        while let Some(start) = queue.pop_front() {
+            if !seen.insert(start) {
+                continue;
+            }
+
+            for edge in edges.iter().filter(|e| e.from == start && e.kind != EdgeKind::Call) {
+                if !entries.contains(&edge.to) {
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+
+        let frame_size = blocks[entry].instructions.first().and_then(frame_size).unwrap_or(0);
+        functions.push(Function { entry: *entry, frame_size, blocks: seen.into_iter().collect() });
+    }
+
+    Cfg { blocks, edges, functions }
+}
+
+impl Cfg {
+    pub fn blocks(&self) -> &BTreeMap<i64, BasicBlock> { &self.blocks }
+
+    pub fn edges(&self) -> &Vec<Edge> { &self.edges }
+
+    pub fn functions(&self) -> &Vec<Function> { &self.functions }
+
+    /// The block containing the instruction at `addr`
+    pub fn block_at(&self, addr: i64) -> Option<&BasicBlock> {
+        self.blocks
+            .range(..=addr)
+            .next_back()
+            .map(|(_, b)| b)
+            .filter(|b| b.instructions.iter().any(|i| i.contains(addr)))
+    }
+
+    pub fn successors(&self, start: i64) -> Vec<i64> {
+        self.edges.iter().filter(|e| e.from == start).map(|e| e.to).collect()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        dot.push_str("digraph cfg {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+
+        for func in &self.functions {
+            dot.push_str(&format!("    subgraph cluster_fn_{} {{\n", func.entry));
+            dot.push_str(&format!("        label=\"fn_{} (frame {})\";\n", func.entry, func.frame_size));
+            for start in &func.blocks {
+                dot.push_str(&format!("        b{};\n", start));
+            }
+            dot.push_str("    }\n");
+        }
+
+        for block in self.blocks.values() {
+            let mut label: String = block.instructions
+                .iter()
+                .map(|i| format!("{}\\l", i.to_string().trim_start()))
+                .collect();
+            if block.indirect {
+                label.push_str("(indirect)\\l");
+            }
+
+            dot.push_str(&format!("    b{} [label=\"{}\"];\n", block.start, label));
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Fallthrough => "",
+                EdgeKind::Jump => " [label=\"jump\"]",
+                EdgeKind::Call => " [label=\"call\", style=dashed]",
+                EdgeKind::CallReturn => " [label=\"return\", style=dotted]",
+            };
+            dot.push_str(&format!("    b{} -> b{}{};\n", edge.from, edge.to, style));
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use intcode::loader::load_str;
+
+    // Pushes 7 as the return address, calls the function at 8 which opens a
+    // 2 cell frame, outputs 42 and returns through [rb+0]
+    fn call_program() -> Program {
+        Program::new(vec![21101, 0, 7, 0, 1105, 1, 8, 99, 109, 2, 104, 42, 109, -2, 2105, 1, 0], 0, vec![], vec![], 0)
+    }
+
+    #[test]
+    fn test_blocks_and_edges() {
+        let cfg = extract_cfg(&call_program());
+
+        assert_eq!(cfg.blocks().keys().cloned().collect::<Vec<i64>>(), vec![0, 7, 8]);
+        assert_eq!(cfg.edges()[..], vec![
+            Edge { from: 0, to: 8, kind: EdgeKind::Call },
+            Edge { from: 0, to: 7, kind: EdgeKind::CallReturn },
+        ][..]);
+        assert!(cfg.blocks()[&8].indirect);
+        assert!(!cfg.blocks()[&0].indirect);
+        assert_eq!(cfg.block_at(12).map(|b| b.start), Some(8));
+        assert_eq!(cfg.block_at(100), None);
+    }
+
+    #[test]
+    fn test_functions() {
+        let cfg = extract_cfg(&call_program());
+
+        assert_eq!(cfg.functions()[..], vec![Function { entry: 8, frame_size: 2, blocks: vec![8] }][..]);
+    }
+
+    #[test]
+    fn test_unmatched_push() {
+        // Stores 10 on the stack without calling anything, then jumps to 11
+        // either through jf [20], 11 or jt 1, 11 right after it
+        let mut ints = vec![21101, 0, 10, 0, 1006, 20, 11, 1105, 1, 11, 99, 104, 1, 99];
+        ints.resize(21, 0);
+        let cfg = extract_cfg(&Program::new(ints, 0, vec![], vec![], 0));
+
+        assert_eq!(cfg.blocks().keys().cloned().collect::<Vec<i64>>(), vec![0, 7, 11]);
+        assert!(cfg.edges().iter().all(|e| e.kind != EdgeKind::CallReturn));
+    }
+
+    #[test]
+    fn test_constant_conditions() {
+        // jf 0, 6 always jumps, so 3 is never reached
+        let cfg = extract_cfg(&Program::new(vec![1106, 0, 6, 104, 1, 99, 99], 0, vec![], vec![], 0));
+
+        assert_eq!(cfg.blocks().keys().cloned().collect::<Vec<i64>>(), vec![0, 6]);
+        assert_eq!(cfg.edges()[..], vec![Edge { from: 0, to: 6, kind: EdgeKind::Jump }][..]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = extract_cfg(&call_program()).to_dot();
+
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("subgraph cluster_fn_8 {"));
+        assert!(dot.contains("b0 -> b8 [label=\"call\", style=dashed];"));
+        assert!(dot.contains("b8 [label=\"8: rbo 2\\l10: out 42\\l12: rbo -2\\l14: jt 1, [rb+0]\\l(indirect)\\l\"];"));
+    }
+
+    #[test]
+    fn test_puzzle_input() {
+        let ints = load_str(&fs::read_to_string("input/2019/day9.txt").unwrap()).unwrap();
+        let cfg = extract_cfg(&Program::new(ints, 0, vec![], vec![], 0));
+
+        assert!(cfg.blocks().len() > 1);
+        assert!(!cfg.functions().is_empty());
+        assert!(cfg.edges().iter().all(|e| cfg.blocks().contains_key(&e.from) && cfg.blocks().contains_key(&e.to)));
+    }
+}
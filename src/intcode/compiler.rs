@@ -0,0 +1,299 @@
+use intcode::run_to_exit;
+use intcode::cfg::extract_cfg;
+use intcode::disasm::Disassembled;
+use intcode::instructions::{Program, ProgramErr, Mode};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand {
+    Imm(i64),
+    Pos(i64),
+    Rel(i64),
+}
+
+fn to_operand(param: &(Mode, i64)) -> Operand {
+    match param.0 {
+        Mode::Immediate => Operand::Imm(param.1),
+        Mode::Parameter => Operand::Pos(param.1),
+        Mode::Relative => Operand::Rel(param.1),
+    }
+}
+
+/// Memory state used while running compiled code, flat over the program
+/// itself and sparse past its end
+struct Machine {
+    ints: Vec<i64>,
+    extra: HashMap<i64, i64>,
+    pointer: i64,
+    rel_base: i64,
+    inputs: VecDeque<i64>,
+    outputs: Vec<i64>,
+}
+
+enum Flow {
+    Next(i64),
+    Halt,
+    /// Hand the current state over to the interpreter
+    Fallback,
+}
+
+impl Machine {
+    fn read(&self, op: Operand) -> i64 {
+        let addr = match op {
+            Operand::Imm(v) => return v,
+            Operand::Pos(addr) => addr,
+            Operand::Rel(offset) => self.rel_base + offset,
+        };
+
+        if addr < 0 {
+            return 0;
+        }
+
+        match self.ints.get(addr as usize) {
+            Some(val) => *val,
+            None => *self.extra.get(&addr).unwrap_or(&0),
+        }
+    }
+
+    fn target(&self, op: Operand) -> Result<i64, ProgramErr> {
+        match op {
+            Operand::Imm(_) => Err(ProgramErr::NeverImmediate),
+            Operand::Pos(addr) => Ok(addr),
+            Operand::Rel(offset) => Ok(self.rel_base + offset),
+        }
+    }
+
+    /// Writes the value, or returns false if the address is code or negative
+    fn write(&mut self, addr: i64, val: i64, code: &[bool]) -> bool {
+        if addr < 0 || code.get(addr as usize) == Some(&true) {
+            return false;
+        }
+
+        match self.ints.get_mut(addr as usize) {
+            Some(slot) => *slot = val,
+            None => { self.extra.insert(addr, val); },
+        }
+
+        true
+    }
+
+    fn to_program(&self, has_exited: bool) -> Program {
+        let mut ints: HashMap<i64, i64> = self.extra.clone();
+        for (i, val) in self.ints.iter().enumerate() {
+            ints.insert(i as i64, *val);
+        }
+
+        let program = Program::new_h(ints, self.pointer, self.outputs.clone(), self.inputs.iter().cloned().collect(), self.rel_base);
+
+        if has_exited { program.exit() } else { program }
+    }
+}
+
+type Op = Box<dyn Fn(&mut Machine, &[bool]) -> Result<Flow, ProgramErr> + Send + Sync>;
+
+fn compile_instruction(instr: &Disassembled) -> Op {
+    let ops: Vec<Operand> = instr.params.iter().map(to_operand).collect();
+    let next = instr.addr + instr.size;
+
+    match instr.opcode {
+        Some(opcode @ 1) | Some(opcode @ 2) | Some(opcode @ 7) | Some(opcode @ 8) => {
+            let (a, b, c) = (ops[0], ops[1], ops[2]);
+
+            Box::new(move |m, code| {
+                let left = m.read(a);
+                let right = m.read(b);
+                let val = match opcode {
+                    1 => left + right,
+                    2 => left * right,
+                    7 => (left < right) as i64,
+                    _ => (left == right) as i64,
+                };
+                let target = m.target(c)?;
+
+                Ok(if m.write(target, val, code) { Flow::Next(next) } else { Flow::Fallback })
+            })
+        },
+        Some(3) => {
+            let a = ops[0];
+
+            Box::new(move |m, code| {
+                let target = m.target(a)?;
+                let val = *m.inputs.front().ok_or(ProgramErr::ExpectedInput)?;
+                if !m.write(target, val, code) {
+                    return Ok(Flow::Fallback);
+                }
+                m.inputs.pop_front();
+
+                Ok(Flow::Next(next))
+            })
+        },
+        Some(4) => {
+            let a = ops[0];
+
+            Box::new(move |m, _| {
+                let val = m.read(a);
+                m.outputs.push(val);
+
+                Ok(Flow::Next(next))
+            })
+        },
+        Some(opcode @ 5) | Some(opcode @ 6) => {
+            let (a, b) = (ops[0], ops[1]);
+
+            Box::new(move |m, _| {
+                let jump = (m.read(a) != 0) == (opcode == 5);
+
+                Ok(Flow::Next(if jump { m.read(b) } else { next }))
+            })
+        },
+        Some(9) => {
+            let a = ops[0];
+
+            Box::new(move |m, _| {
+                m.rel_base += m.read(a);
+
+                Ok(Flow::Next(next))
+            })
+        },
+        _ => Box::new(|_, _| Ok(Flow::Halt)),
+    }
+}
+
+/// An Intcode program translated ahead of time into one closure per reachable
+/// instruction. Writes into the translated region, or jumps outside of it,
+/// continue in the interpreter so self-modifying programs still behave.
+pub struct CompiledProgram {
+    ints: Vec<i64>,
+    ops: Vec<Option<Op>>,
+    code: Vec<bool>,
+}
+
+/// ```
+/// use aoc_2019::intcode::run_program;
+/// use aoc_2019::intcode::compiler::compile;
+///
+/// // Outputs 999, 1000 or 1001 for inputs below, equal to or above 8
+/// let ints = vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99];
+/// let compiled = compile(&ints);
+///
+/// for input in 6..10 {
+///     assert_eq!(compiled.run(&[input]).unwrap(), run_program(&ints, &vec![input]).unwrap());
+/// }
+/// ```
+pub fn compile(program_ints: &[i64]) -> CompiledProgram {
+    let program = Program::new(program_ints.to_vec(), 0, vec![], vec![], 0);
+    let cfg = extract_cfg(&program);
+
+    let instructions: Vec<&Disassembled> = cfg.blocks().values().flat_map(|b| b.instructions.iter()).collect();
+    let end = instructions.iter().map(|i| i.addr + i.size).max().unwrap_or(0) as usize;
+
+    let mut ops: Vec<Option<Op>> = (0..end).map(|_| None).collect();
+    let mut code = vec![false; end];
+
+    for instr in instructions {
+        for addr in instr.addr..(instr.addr + instr.size) {
+            code[addr as usize] = true;
+        }
+        ops[instr.addr as usize] = Some(compile_instruction(instr));
+    }
+
+    CompiledProgram { ints: program_ints.to_vec(), ops, code }
+}
+
+impl CompiledProgram {
+    /// Number of instructions that were translated
+    pub fn compiled_len(&self) -> usize {
+        self.ops.iter().filter(|op| op.is_some()).count()
+    }
+
+    /// Runs until the program halts, with the same result as `run_program`
+    pub fn run(&self, inputs: &[i64]) -> Result<Program, ProgramErr> {
+        let mut m = Machine {
+            ints: self.ints.clone(),
+            extra: HashMap::new(),
+            pointer: 0,
+            rel_base: 0,
+            inputs: inputs.iter().cloned().collect(),
+            outputs: vec![],
+        };
+
+        loop {
+            let op = if m.pointer >= 0 { self.ops.get(m.pointer as usize) } else { None };
+
+            let flow = match op {
+                Some(Some(op)) => op(&mut m, &self.code)?,
+                _ => Flow::Fallback,
+            };
+
+            match flow {
+                Flow::Next(pointer) => m.pointer = pointer,
+                Flow::Halt => return Ok(m.to_program(true)),
+                Flow::Fallback => return run_to_exit(m.to_program(false)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::run_program;
+    use intcode::loader::load_str;
+    use std::fs;
+
+    fn assert_same(ints: Vec<i64>, inputs: Vec<i64>) {
+        assert_eq!(compile(&ints).run(&inputs).unwrap(), run_program(&ints, &inputs).unwrap());
+    }
+
+    #[test]
+    fn test_self_modifying_fallback() {
+        assert_same(vec![1, 0, 0, 0, 99], vec![]);
+        assert_same(vec![2, 3, 0, 3, 99], vec![]);
+        assert_same(vec![2, 4, 4, 5, 99, 0], vec![]);
+        assert_same(vec![1, 1, 1, 4, 99, 5, 6, 0, 99], vec![]);
+    }
+
+    #[test]
+    fn test_jumps() {
+        assert_same(vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9], vec![0]);
+        assert_same(vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9], vec![999]);
+        assert_same(vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1], vec![999]);
+    }
+
+    #[test]
+    fn test_relative_mode() {
+        let quine = vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+        let res = compile(&quine).run(&[]).unwrap();
+
+        assert_eq!(res.outputs(), quine);
+        assert_same(quine, vec![]);
+        assert_same(vec![104, 1125899906842624, 99], vec![]);
+        assert_same(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0], vec![]);
+    }
+
+    #[test]
+    fn test_large_address() {
+        // Writes the input far past the end of the program and echoes it back
+        let ints = vec![3, 1_000_000_000_000, 4, 1_000_000_000_000, 99];
+        let res = compile(&ints).run(&[42]).unwrap();
+
+        assert_eq!(res.outputs(), vec![42]);
+        assert_same(ints, vec![42]);
+        assert_same(vec![1101, 3, 4, 1_000_000_000_000, 1001, 1_000_000_000_000, 1, 1_000_000_000_001, 4, 1_000_000_000_001, 99], vec![]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(compile(&[3, 0, 99]).run(&[]).is_err());
+        assert!(compile(&[1101, 1, 1, 0]).run(&[]).is_err());
+    }
+
+    #[test]
+    fn test_day5() {
+        let ints = load_str(&fs::read_to_string("input/2019/day5.txt").unwrap()).unwrap();
+
+        assert!(compile(&ints).compiled_len() > 0);
+        assert_same(ints.clone(), vec![1]);
+        assert_same(ints, vec![5]);
+    }
+}
@@ -0,0 +1,245 @@
+// Randomized conformance tests: every opcode is run with every combination of
+// parameter modes and checked against a minimal reference interpreter.
+
+use intcode::step;
+use intcode::cache::{DecodeCache, step_cached};
+use intcode::instructions::Program;
+
+const MEM_SIZE: i64 = 64;
+const TRIALS: usize = 50;
+
+/// xorshift64, so failures can be reproduced from the seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn range(&mut self, from: i64, to: i64) -> i64 {
+        from + (self.next() % (to - from) as u64) as i64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    ints: Vec<i64>,
+    pointer: i64,
+    rel_base: i64,
+    inputs: Vec<i64>,
+    outputs: Vec<i64>,
+    halted: bool,
+}
+
+fn param_count(opcode: i64) -> usize {
+    match opcode {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 | 9 => 1,
+        _ => 0,
+    }
+}
+
+fn writes_last(opcode: i64) -> bool {
+    matches!(opcode, 1 | 2 | 3 | 7 | 8)
+}
+
+/// Executes a single instruction, or `None` where the interpreter should fail
+fn reference_step(state: &State) -> Option<State> {
+    let mut s = state.clone();
+    let raw = s.ints[s.pointer as usize];
+    let opcode = raw % 100;
+    let cnt = param_count(opcode);
+    let modes: Vec<i64> = (0..cnt).map(|i| (raw / 10i64.pow(i as u32 + 2)) % 10).collect();
+    let params: Vec<i64> = (0..cnt).map(|i| s.ints[s.pointer as usize + 1 + i]).collect();
+
+    let addr = |i: usize| match modes[i] {
+        0 => Some(params[i]),
+        2 => Some(state.rel_base + params[i]),
+        _ => None,
+    };
+    let val = |i: usize| addr(i).map(|a| state.ints[a as usize]).unwrap_or(params[i]);
+    let next = s.pointer + 1 + cnt as i64;
+
+    match opcode {
+        1 | 2 | 7 | 8 => {
+            let res = match opcode {
+                1 => val(0) + val(1),
+                2 => val(0) * val(1),
+                7 => (val(0) < val(1)) as i64,
+                _ => (val(0) == val(1)) as i64,
+            };
+            s.ints[addr(2)? as usize] = res;
+            s.pointer = next;
+        },
+        3 => {
+            s.ints[addr(0)? as usize] = s.inputs.remove(0);
+            s.pointer = next;
+        },
+        4 => {
+            s.outputs.push(val(0));
+            s.pointer = next;
+        },
+        5 => s.pointer = if val(0) != 0 { val(1) } else { next },
+        6 => s.pointer = if val(0) == 0 { val(1) } else { next },
+        9 => {
+            s.rel_base += val(0);
+            s.pointer = next;
+        },
+        _ => s.halted = true,
+    }
+
+    Some(s)
+}
+
+fn to_state(program: &Program) -> State {
+    State {
+        ints: (0..MEM_SIZE).map(|i| program.get_int(i)).collect(),
+        pointer: program.pointer(),
+        rel_base: program.rel_base(),
+        inputs: program.inputs(),
+        outputs: program.outputs(),
+        halted: program.has_exited(),
+    }
+}
+
+/// Memory starts with `rbo <rel_base>` followed by the instruction under test,
+/// the rest is random data. Operands always address cells inside memory.
+fn generate(rng: &mut Rng, opcode: i64, modes: &[i64]) -> Program {
+    let rel_base = rng.range(0, MEM_SIZE / 2);
+    let mut ints: Vec<i64> = (0..MEM_SIZE).map(|_| rng.range(-1000, 1000)).collect();
+
+    ints[0] = 109;
+    ints[1] = rel_base;
+    ints[2] = opcode + modes.iter().enumerate().map(|(i, m)| m * 10i64.pow(i as u32 + 2)).sum::<i64>();
+
+    for (i, mode) in modes.iter().enumerate() {
+        ints[3 + i] = match mode {
+            0 => rng.range(0, MEM_SIZE),
+            2 => rng.range(-rel_base, MEM_SIZE - rel_base),
+            _ => rng.range(-1000, 1000),
+        };
+    }
+
+    Program::new(ints, 0, vec![], vec![rng.range(-1000, 1000)], 0)
+}
+
+fn mode_combinations(cnt: usize) -> Vec<Vec<i64>> {
+    (0..cnt).fold(vec![vec![]], |acc, _| {
+        acc.iter()
+            .flat_map(|prefix| (0..3).map(move |m| {
+                let mut next = prefix.clone();
+                next.push(m);
+                next
+            }))
+            .collect()
+    })
+}
+
+/// Runs `step_fn` over every opcode and mode combination, comparing the state
+/// after the instruction under test with the reference interpreter
+fn check_conformance<F>(seed: u64, mut step_fn: F) where F: FnMut(Program) -> Option<Program> {
+    let mut rng = Rng(seed);
+
+    for opcode in &[1, 2, 3, 4, 5, 6, 7, 8, 9, 99] {
+        for modes in mode_combinations(param_count(*opcode)) {
+            for _ in 0..TRIALS {
+                let program = generate(&mut rng, *opcode, &modes);
+                let after_rbo = step_fn(program.clone()).expect("rbo should never fail");
+
+                let expected = reference_step(&to_state(&after_rbo));
+                let actual = step_fn(after_rbo).map(|p| to_state(&p));
+
+                assert_eq!(
+                    actual,
+                    expected,
+                    "opcode {} with modes {:?} (seed {}) diverged for {:?}",
+                    opcode, modes, seed, to_state(&program).ints
+                );
+
+                if writes_last(*opcode) && modes.last() == Some(&1) {
+                    assert!(expected.is_none());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mode_combinations() {
+    assert_eq!(mode_combinations(0).len(), 1);
+    assert_eq!(mode_combinations(3).len(), 27);
+    assert!(mode_combinations(2).contains(&vec![2, 1]));
+}
+
+#[test]
+fn test_step_conformance() {
+    for seed in &[1, 0x2019, 0xdead_beef] {
+        check_conformance(*seed, |p| step(p).ok());
+    }
+}
+
+/// Memory loaded by the harness, rather than written by an instruction, counts
+/// as patched by hand, so cached instructions covering any change are dropped
+fn invalidate_changed(cache: &mut DecodeCache, memory: &mut Vec<i64>, program: &Program) {
+    let ints = to_state(program).ints;
+
+    for addr in (0..MEM_SIZE).filter(|a| ints[*a as usize] != memory[*a as usize]) {
+        cache.invalidate(addr);
+    }
+
+    *memory = ints;
+}
+
+#[test]
+fn test_step_cached_conformance() {
+    let mut cache = DecodeCache::new();
+    let mut memory = vec![0; MEM_SIZE as usize];
+
+    for seed in &[7, 0x1202] {
+        check_conformance(*seed, |p| {
+            invalidate_changed(&mut cache, &mut memory, &p);
+            let first = step_cached(p.clone(), &mut cache).ok();
+
+            // The same instruction again is a cache hit, unless the first run
+            // overwrote it and `step_cached` invalidated it
+            if let Some(res) = &first {
+                memory = to_state(res).ints;
+            }
+            invalidate_changed(&mut cache, &mut memory, &p);
+            let second = step_cached(p, &mut cache).ok();
+
+            assert_eq!(first, second);
+            if let Some(res) = &second {
+                memory = to_state(res).ints;
+            }
+
+            second
+        });
+    }
+
+    assert!(cache.hits() > cache.misses());
+}
+
+#[test]
+fn test_step_cached_self_modifying() {
+    // Increments the operand of the already decoded output at 4 on every
+    // pass, outputting 1, 2 and 3
+    let ints = vec![1001, 5, 1, 5, 104, 0, 1008, 5, 3, 20, 1006, 20, 0, 99, 0, 0, 0, 0, 0, 0, 0];
+    let mut cached = Program::new(ints.clone(), 0, vec![], vec![], 0);
+    let mut plain = Program::new(ints, 0, vec![], vec![], 0);
+    let mut cache = DecodeCache::new();
+
+    while !plain.has_exited() {
+        cached = step_cached(cached, &mut cache).unwrap();
+        plain = step(plain).unwrap();
+
+        assert_eq!(cached, plain);
+    }
+
+    assert_eq!(plain.outputs(), vec![1, 2, 3]);
+    assert!(cache.hits() > 0);
+}
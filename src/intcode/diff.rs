@@ -0,0 +1,124 @@
+use intcode::run_program;
+use intcode::disasm::{linear_sweep, Disassembled};
+use intcode::instructions::{Program, ProgramErr};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryChange {
+    pub addr: i64,
+    pub before: i64,
+    pub after: i64,
+}
+
+/// Lists every address whose value differs between two snapshots, ordered by
+/// address. Unset addresses count as 0.
+///
+/// ```
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::diff::{diff_memory, MemoryChange};
+///
+/// let before = Program::new(vec![1, 0, 0, 0, 99], 0, vec![], vec![], 0);
+/// let after = before.set_ints(0, 2).set_ints(10, 5);
+///
+/// assert_eq!(diff_memory(&before, &after), vec![
+///     MemoryChange { addr: 0, before: 1, after: 2 },
+///     MemoryChange { addr: 10, before: 0, after: 5 },
+/// ]);
+/// ```
+pub fn diff_memory(before: &Program, after: &Program) -> Vec<MemoryChange> {
+    let before_ints = before.ints();
+    let after_ints = after.ints();
+
+    let addrs: HashSet<&i64> = before_ints.keys().chain(after_ints.keys()).collect();
+    let mut changes: Vec<MemoryChange> = addrs
+        .into_iter()
+        .map(|addr| MemoryChange { addr: *addr, before: before.get_int(*addr), after: after.get_int(*addr) })
+        .filter(|change| change.before != change.after)
+        .collect();
+
+    changes.sort_by_key(|change| change.addr);
+
+    changes
+}
+
+/// Runs the program and diffs its memory from before the run to after it halted
+pub fn diff_run(program_ints: &[i64], inputs: &[i64]) -> Result<Vec<MemoryChange>, ProgramErr> {
+    let before = Program::new(program_ints.to_vec(), 0, vec![], vec![], 0);
+    let after = run_program(program_ints, inputs)?;
+
+    Ok(diff_memory(&before, &after))
+}
+
+/// Formats changes one per line, optionally followed by the instruction in
+/// `context` covering the changed address
+///
+/// ```
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::diff::{diff_memory, format_diff};
+///
+/// let before = Program::new(vec![1, 0, 0, 0, 99], 0, vec![], vec![], 0);
+/// let after = before.set_ints(3, 2);
+///
+/// assert_eq!(
+///     format_diff(&diff_memory(&before, &after), Some(&before)),
+///     "    3: 0 -> 2    (0: add [0], [0], [0])\n"
+/// );
+/// ```
+pub fn format_diff(changes: &[MemoryChange], context: Option<&Program>) -> String {
+    let sweep: Vec<Disassembled> = match context {
+        Some(program) => {
+            let len = program.ints().keys().max().map(|max| max + 1).unwrap_or(0);
+            linear_sweep(program, 0, len)
+        },
+        None => vec![],
+    };
+
+    let mut out = String::new();
+
+    for change in changes {
+        out.push_str(&format!("{:>5}: {} -> {}", change.addr, change.before, change.after));
+
+        if let Some(instr) = sweep.iter().find(|instr| instr.contains(change.addr)) {
+            out.push_str(&format!("    ({})", instr.to_string().trim_start()));
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use day2::input_generator;
+
+    #[test]
+    fn test_diff_memory_identical() {
+        let program = Program::new(vec![1, 0, 0, 0, 99], 0, vec![], vec![], 0);
+
+        assert!(diff_memory(&program, &program.set_pointer(4)).is_empty());
+        assert!(diff_memory(&program, &program.set_ints(7, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_diff_run() {
+        assert_eq!(diff_run(&[1, 1, 1, 4, 99, 5, 6, 0, 99], &[]).unwrap(), vec![
+            MemoryChange { addr: 0, before: 1, after: 30 },
+            MemoryChange { addr: 4, before: 99, after: 2 },
+        ]);
+    }
+
+    #[test]
+    fn test_day2_noun_changes() {
+        let ints = input_generator(&fs::read_to_string("input/2019/day2.txt").unwrap());
+        let program = Program::new(ints.clone(), 0, vec![], vec![], 0);
+        let run_with = |noun: i64| run_program(&program.set_ints(1, noun).set_ints(2, 2).as_vec(0, ints.len() as i64), &[]).unwrap();
+
+        let changes = diff_memory(&run_with(12), &run_with(13));
+
+        assert_eq!(changes.first().map(|c| c.addr), Some(0));
+        assert!(format_diff(&changes, Some(&program)).starts_with("    0: "));
+    }
+}
@@ -0,0 +1,133 @@
+use intcode::instructions::{Program, Mode, parse_opcode};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disassembled {
+    pub addr: i64,
+    pub size: i64,
+    pub opcode: Option<i64>,
+    pub params: Vec<(Mode, i64)>,
+}
+
+/// Mnemonic and parameter count for an opcode
+pub fn opcode_info(opcode: i64) -> Option<(&'static str, usize)> {
+    match opcode {
+        1 => Some(("add", 3)),
+        2 => Some(("mul", 3)),
+        3 => Some(("in", 1)),
+        4 => Some(("out", 1)),
+        5 => Some(("jt", 2)),
+        6 => Some(("jf", 2)),
+        7 => Some(("lt", 3)),
+        8 => Some(("eq", 3)),
+        9 => Some(("rbo", 1)),
+        99 => Some(("hlt", 0)),
+        _ => None,
+    }
+}
+
+fn format_param(param: &(Mode, i64)) -> String {
+    match param.0 {
+        Mode::Parameter => format!("[{}]", param.1),
+        Mode::Immediate => format!("{}", param.1),
+        Mode::Relative => format!("[rb{:+}]", param.1),
+    }
+}
+
+impl Disassembled {
+    pub fn is_data(&self) -> bool { self.opcode.is_none() }
+
+    pub fn contains(&self, addr: i64) -> bool {
+        addr >= self.addr && addr < self.addr + self.size
+    }
+}
+
+impl fmt::Display for Disassembled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.opcode.and_then(opcode_info) {
+            Some((name, _)) if self.params.is_empty() => write!(f, "{:>5}: {}", self.addr, name),
+            Some((name, _)) => {
+                let params: Vec<String> = self.params.iter().map(format_param).collect();
+                write!(f, "{:>5}: {} {}", self.addr, name, params.join(", "))
+            },
+            None => write!(f, "{:>5}: data {}", self.addr, self.params.first().map(|p| p.1).unwrap_or(0)),
+        }
+    }
+}
+
+/// Decodes the instruction at `addr`, or a single data cell if it isn't a valid instruction
+///
+/// ```
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::disasm::disassemble;
+///
+/// let program = Program::new(vec![1002, 4, 3, 4, 33], 0, vec![], vec![], 0);
+///
+/// assert_eq!(disassemble(&program, 0).to_string(), "    0: mul [4], 3, [4]");
+/// assert_eq!(disassemble(&program, 4).to_string(), "    4: data 33");
+/// ```
+pub fn disassemble(program: &Program, addr: i64) -> Disassembled {
+    let raw = program.get_int(addr);
+    let decoded = parse_opcode(raw)
+        .ok()
+        .and_then(|op| opcode_info(op.opcode).map(|(_, cnt)| (op, cnt)));
+
+    match decoded {
+        Some((op, cnt)) => {
+            let modes = [op.a, op.b, op.c];
+            let params = modes
+                .iter()
+                .take(cnt)
+                .enumerate()
+                .map(|(i, mode)| (mode.clone(), program.get_int(addr + 1 + i as i64)))
+                .collect();
+
+            Disassembled { addr, size: 1 + cnt as i64, opcode: Some(op.opcode), params }
+        },
+        None => Disassembled { addr, size: 1, opcode: None, params: vec![(Mode::Immediate, raw)] },
+    }
+}
+
+/// Disassembles `len` addresses in order from `start`, without following jumps
+pub fn linear_sweep(program: &Program, start: i64, len: i64) -> Vec<Disassembled> {
+    let mut out = vec![];
+    let mut addr = start;
+
+    while addr < start + len {
+        let instr = disassemble(program, addr);
+        addr += instr.size;
+        out.push(instr);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        let program = Program::new(vec![21101, -1, 7, 3, 109, 19, 99], 0, vec![], vec![], 0);
+
+        assert_eq!(disassemble(&program, 0), Disassembled {
+            addr: 0,
+            size: 4,
+            opcode: Some(1),
+            params: vec![(Mode::Immediate, -1), (Mode::Immediate, 7), (Mode::Relative, 3)],
+        });
+        assert_eq!(disassemble(&program, 0).to_string(), "    0: add -1, 7, [rb+3]");
+        assert_eq!(disassemble(&program, 6).to_string(), "    6: hlt");
+        assert!(disassemble(&program, 100).is_data());
+    }
+
+    #[test]
+    fn test_linear_sweep() {
+        let program = Program::new(vec![21101, -1, 7, 3, 109, 19, 99], 0, vec![], vec![], 0);
+        let sweep = linear_sweep(&program, 0, 7);
+
+        assert_eq!(sweep.iter().map(|d| d.addr).collect::<Vec<i64>>(), vec![0, 4, 6]);
+        assert!(sweep[1].contains(5));
+        assert!(!sweep[1].contains(6));
+    }
+}
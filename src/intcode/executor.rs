@@ -0,0 +1,271 @@
+use intcode::run_until_blocked;
+use intcode::instructions::{Program, ProgramErr};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    queue: VecDeque<i64>,
+    waker: Option<Waker>,
+}
+
+/// Single consumer queue of ints connecting machines on the same executor.
+/// Clones share the same queue.
+#[derive(Debug, Clone, Default)]
+pub struct Channel {
+    state: Rc<RefCell<ChannelState>>,
+}
+
+impl Channel {
+    pub fn new() -> Channel {
+        Channel::default()
+    }
+
+    pub fn send(&self, val: i64) {
+        let mut state = self.state.borrow_mut();
+        state.queue.push_back(val);
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Takes a value without waiting
+    pub fn try_recv(&self) -> Option<i64> {
+        self.state.borrow_mut().queue.pop_front()
+    }
+
+    /// Resolves to the next value once one has been sent
+    pub fn recv(&self) -> Recv {
+        Recv { channel: self.clone() }
+    }
+
+    pub fn len(&self) -> usize { self.state.borrow().queue.len() }
+
+    pub fn is_empty(&self) -> bool { self.state.borrow().queue.is_empty() }
+}
+
+pub struct Recv {
+    channel: Channel,
+}
+
+impl Future for Recv {
+    type Output = i64;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<i64> {
+        let mut state = self.channel.state.borrow_mut();
+
+        match state.queue.pop_front() {
+            Some(val) => Poll::Ready(val),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+/// A program that reads its inputs from one channel and sends its outputs to
+/// another, yielding to the executor whenever its input is empty. Resolves to
+/// the halted program.
+pub struct Machine {
+    program: Option<Program>,
+    input: Recv,
+    output: Channel,
+    sent: usize,
+}
+
+impl Machine {
+    pub fn new(program: Program, input: &Channel, output: &Channel) -> Machine {
+        let sent = program.output_count();
+
+        Machine { program: Some(program), input: input.recv(), output: output.clone(), sent }
+    }
+}
+
+impl Future for Machine {
+    type Output = Result<Program, ProgramErr>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut program = self.program.take().expect("Machine polled after completion");
+
+        loop {
+            program = run_until_blocked(program)?;
+
+            for val in program.outputs_since(self.sent) {
+                self.output.send(*val);
+            }
+            self.sent = program.output_count();
+
+            if program.has_exited() {
+                return Poll::Ready(Ok(program));
+            }
+
+            match Pin::new(&mut self.input).poll(cx) {
+                Poll::Ready(val) => program = program.push_input(val),
+                Poll::Pending => {
+                    self.program = Some(program);
+                    return Poll::Pending;
+                },
+            }
+        }
+    }
+}
+
+/// Result slot of a spawned task
+pub struct JoinHandle<T> {
+    result: Rc<RefCell<Option<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// The task's result, if it has finished and hasn't been taken yet
+    pub fn take(&self) -> Option<T> {
+        self.result.borrow_mut().take()
+    }
+}
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.id);
+    }
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// Minimal single-threaded executor. Tasks only get polled again after their
+/// waker fires, so blocked machines cost nothing while they wait.
+#[derive(Default)]
+pub struct Executor {
+    tasks: Vec<Option<Task>>,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Executor {
+    pub fn new() -> Executor {
+        Executor::default()
+    }
+
+    pub fn spawn<F, T>(&mut self, future: F) -> JoinHandle<T> where F: Future<Output = T> + 'static, T: 'static {
+        let result = Rc::new(RefCell::new(None));
+        let slot = result.clone();
+        let mut future = Box::pin(future);
+
+        let task = std::future::poll_fn(move |cx| {
+            future.as_mut().poll(cx).map(|val| *slot.borrow_mut() = Some(val))
+        });
+
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(Box::pin(task)));
+
+        JoinHandle { result }
+    }
+
+    pub fn spawn_machine(&mut self, program: Program, input: &Channel, output: &Channel) -> JoinHandle<Result<Program, ProgramErr>> {
+        self.spawn(Machine::new(program, input, output))
+    }
+
+    /// Polls tasks until none can make progress. Returns the number of tasks
+    /// still blocked, so anything above zero means they deadlocked.
+    pub fn run(&mut self) -> usize {
+        loop {
+            let next = self.ready.lock().unwrap().pop_front();
+            let id = match next {
+                Some(id) => id,
+                None => break,
+            };
+
+            let waker = Waker::from(Arc::new(TaskWaker { id, ready: self.ready.clone() }));
+            let done = match self.tasks[id].as_mut() {
+                Some(task) => task.as_mut().poll(&mut Context::from_waker(&waker)).is_ready(),
+                None => false,
+            };
+
+            if done {
+                self.tasks[id] = None;
+            }
+        }
+
+        self.tasks.iter().filter(|t| t.is_some()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amplifier_loop(ints: &[i64], phases: &[i64]) -> i64 {
+        let mut executor = Executor::new();
+        let channels: Vec<Channel> = phases.iter().map(|_| Channel::new()).collect();
+
+        for (i, phase) in phases.iter().enumerate() {
+            channels[i].send(*phase);
+        }
+        channels[0].send(0);
+
+        let handles: Vec<_> = (0..phases.len()).map(|i| {
+            let program = Program::new(ints.to_vec(), 0, vec![], vec![], 0);
+            executor.spawn_machine(program, &channels[i], &channels[(i + 1) % phases.len()])
+        }).collect();
+
+        assert_eq!(executor.run(), 0);
+        for handle in handles {
+            assert!(handle.take().unwrap().unwrap().has_exited());
+        }
+
+        channels[0].try_recv().unwrap()
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        let ints = vec![3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5];
+
+        assert_eq!(amplifier_loop(&ints, &[9, 8, 7, 6, 5]), 139629729);
+    }
+
+    #[test]
+    fn test_many_machines() {
+        // Each machine adds one to its input
+        let ints = [3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+        let mut executor = Executor::new();
+        let first = Channel::new();
+        let mut last = first.clone();
+
+        for _ in 0..50 {
+            let next = Channel::new();
+            executor.spawn_machine(Program::new(ints.to_vec(), 0, vec![], vec![], 0), &last, &next);
+            last = next;
+        }
+
+        assert_eq!(executor.run(), 50);
+        first.send(0);
+        assert_eq!(executor.run(), 0);
+        assert_eq!(last.try_recv(), Some(50));
+    }
+
+    #[test]
+    fn test_blocked_until_sent() {
+        let mut executor = Executor::new();
+        let (input, output) = (Channel::new(), Channel::new());
+        let handle = executor.spawn_machine(Program::new(vec![3, 0, 3, 0, 4, 0, 99], 0, vec![], vec![], 0), &input, &output);
+        let reader = executor.spawn(output.recv());
+
+        input.send(1);
+        assert_eq!(executor.run(), 2);
+        assert!(handle.take().is_none());
+
+        input.send(2);
+        assert_eq!(executor.run(), 0);
+        assert_eq!(reader.take(), Some(2));
+        assert_eq!(handle.take().unwrap().unwrap().get_int(0), 2);
+    }
+}
@@ -0,0 +1,335 @@
+use day11::{Coords, Direction, move_robot};
+use intcode::run_until_blocked;
+use intcode::instructions::{Program, ProgramErr};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+#[derive(Debug, Fail)]
+pub enum ExploreErr {
+    #[fail(display = "{}", _0)]
+    Program(#[cause] ProgramErr),
+    #[fail(display = "Droid replied with unknown status {}", _0)]
+    InvalidStatus(i64),
+    #[fail(display = "Droid halted before the area was mapped")]
+    Halted,
+    #[fail(display = "Droid replied with {} outputs to a single move", _0)]
+    StatusCount(usize),
+    #[fail(display = "Droid couldn't move back the way it came, found {:?} instead", _0)]
+    Backtrack(Tile),
+}
+
+impl From<ProgramErr> for ExploreErr {
+    fn from(err: ProgramErr) -> ExploreErr {
+        ExploreErr::Program(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tile {
+    Wall,
+    Open,
+    Target,
+}
+
+fn direction_to_command(dir: Direction) -> i64 {
+    match dir {
+        Direction::Up => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+        Direction::Right => 4,
+    }
+}
+
+fn status_to_tile(status: i64) -> Result<Tile, ExploreErr> {
+    match status {
+        0 => Ok(Tile::Wall),
+        1 => Ok(Tile::Open),
+        2 => Ok(Tile::Target),
+        _ => Err(ExploreErr::InvalidStatus(status)),
+    }
+}
+
+/// Intcode droid taking one movement command (1 north, 2 south, 3 west,
+/// 4 east) per input and replying with 0 for a wall, 1 for moved or 2 for
+/// moved onto the target
+#[derive(Debug, Clone)]
+pub struct Droid {
+    program: Program,
+}
+
+impl Droid {
+    pub fn new(program_ints: &[i64]) -> Droid {
+        Droid { program: Program::new(program_ints.to_vec(), 0, vec![], vec![], 0) }
+    }
+
+    /// Tries to move, returning what was found in that direction. The droid
+    /// has to reply with exactly one status per move.
+    pub fn step(&mut self, dir: Direction) -> Result<Tile, ExploreErr> {
+        let seen = self.program.output_count();
+        let program = std::mem::take(&mut self.program).push_input(direction_to_command(dir));
+        self.program = run_until_blocked(program)?;
+
+        if self.program.has_exited() {
+            return Err(ExploreErr::Halted);
+        }
+
+        match *self.program.outputs_since(seen) {
+            [status] => status_to_tile(status),
+            ref new => Err(ExploreErr::StatusCount(new.len())),
+        }
+    }
+}
+
+/// Everything a droid has seen, relative to where it started
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaMap {
+    tiles: HashMap<Coords, Tile>,
+}
+
+impl AreaMap {
+    pub fn tiles(&self) -> &HashMap<Coords, Tile> { &self.tiles }
+
+    pub fn get(&self, coords: Coords) -> Option<Tile> {
+        self.tiles.get(&coords).cloned()
+    }
+
+    pub fn target(&self) -> Option<Coords> {
+        self.tiles.iter().find(|(_, t)| **t == Tile::Target).map(|(c, _)| *c)
+    }
+
+    /// Breadth-first distance to every open tile reachable from `from`
+    pub fn distances(&self, from: Coords) -> HashMap<Coords, usize> {
+        let mut dist = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        if self.get(from).map(|t| t != Tile::Wall).unwrap_or(false) {
+            dist.insert(from, 0);
+            queue.push_back(from);
+        }
+
+        while let Some(pos) = queue.pop_front() {
+            for dir in Direction::all().iter() {
+                let next = move_robot(pos, *dir);
+                let open = self.get(next).map(|t| t != Tile::Wall).unwrap_or(false);
+
+                if open && !dist.contains_key(&next) {
+                    dist.insert(next, dist[&pos] + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        dist
+    }
+
+    /// Moves along a shortest path between two tiles
+    pub fn shortest_path(&self, from: Coords, to: Coords) -> Option<Vec<Direction>> {
+        let dist = self.distances(to);
+        let mut pos = from;
+        let mut path = vec![];
+
+        while pos != to {
+            let d = *dist.get(&pos)?;
+            let dir = *Direction::all().iter().find(|dir| dist.get(&move_robot(pos, **dir)) == Some(&(d - 1)))?;
+
+            path.push(dir);
+            pos = move_robot(pos, dir);
+        }
+
+        Some(path)
+    }
+
+    /// Steps needed to fill every reachable tile when spreading from `from`
+    pub fn flood_fill(&self, from: Coords) -> usize {
+        self.distances(from).values().cloned().max().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for AreaMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let xs: Vec<i64> = self.tiles.keys().map(|c| c.0).collect();
+        let ys: Vec<i64> = self.tiles.keys().map(|c| c.1).collect();
+        let (min_x, max_x) = (*xs.iter().min().unwrap_or(&0), *xs.iter().max().unwrap_or(&0));
+        let (min_y, max_y) = (*ys.iter().min().unwrap_or(&0), *ys.iter().max().unwrap_or(&0));
+
+        for y in (min_y..=max_y).rev() {
+            let row: String = (min_x..=max_x).map(|x| match self.get((x, y)) {
+                _ if (x, y) == (0, 0) => 'S',
+                Some(Tile::Wall) => '#',
+                Some(Tile::Open) => '.',
+                Some(Tile::Target) => 'O',
+                None => ' ',
+            }).collect();
+
+            writeln!(f, "{}", row)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps everything reachable from the start with a backtracking depth-first
+/// search. `step` tries to move one tile and reports what is there; walls
+/// don't move the explorer. Fails with `ExploreErr::Backtrack` if moving back
+/// doesn't land on the tile seen there before.
+///
+/// ```
+/// use aoc_2019::day11::{Coords, move_robot};
+/// use aoc_2019::intcode::explorer::{explore_with, ExploreErr, Tile};
+///
+/// // A corridor running east from the start, ending in the target
+/// let mut pos: Coords = (0, 0);
+/// let map = explore_with(|dir| {
+///     let next = move_robot(pos, dir);
+///     let tile = match next {
+///         (x, 0) if x >= 0 && x < 3 => Tile::Open,
+///         (3, 0) => Tile::Target,
+///         _ => return Ok::<Tile, ExploreErr>(Tile::Wall),
+///     };
+///     pos = next;
+///     Ok(tile)
+/// }).unwrap();
+///
+/// assert_eq!(map.target(), Some((3, 0)));
+/// assert_eq!(map.shortest_path((0, 0), (3, 0)).unwrap().len(), 3);
+/// ```
+pub fn explore_with<F, E>(mut step: F) -> Result<AreaMap, E>
+where
+    F: FnMut(Direction) -> Result<Tile, E>,
+    E: From<ExploreErr>,
+{
+    let mut tiles = HashMap::new();
+    tiles.insert((0, 0), Tile::Open);
+
+    let mut pos = (0, 0);
+    let mut trail: Vec<Direction> = vec![];
+
+    loop {
+        let unknown = Direction::all().iter().cloned().find(|dir| !tiles.contains_key(&move_robot(pos, *dir)));
+
+        match unknown {
+            Some(dir) => {
+                let next = move_robot(pos, dir);
+                let tile = step(dir)?;
+                tiles.insert(next, tile);
+
+                if tile != Tile::Wall {
+                    pos = next;
+                    trail.push(dir);
+                }
+            },
+            None => match trail.pop() {
+                Some(dir) => {
+                    let back = move_robot(pos, dir.reverse());
+                    let tile = step(dir.reverse())?;
+
+                    if tiles.get(&back) != Some(&tile) {
+                        return Err(ExploreErr::Backtrack(tile).into());
+                    }
+                    pos = back;
+                },
+                None => return Ok(AreaMap { tiles }),
+            },
+        }
+    }
+}
+
+/// Maps the area around an Intcode droid
+pub fn explore(program_ints: &[i64]) -> Result<AreaMap, ExploreErr> {
+    let mut droid = Droid::new(program_ints);
+
+    explore_with(|dir| droid.step(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: [&str; 5] = [
+        " ##   ",
+        "#..## ",
+        "#.#..#",
+        "#.O.# ",
+        " ###  ",
+    ];
+
+    /// Start is at the top left open tile, y grows upwards like in day11
+    fn maze_step(pos: &mut Coords, dir: Direction) -> Result<Tile, ExploreErr> {
+        let next = move_robot(*pos, dir);
+        let (col, row) = ((next.0 + 1) as usize, (1 - next.1) as usize);
+
+        let tile = match MAZE.get(row).and_then(|r| r.chars().nth(col)) {
+            Some('.') => Tile::Open,
+            Some('O') => Tile::Target,
+            _ => return Ok(Tile::Wall),
+        };
+        *pos = next;
+
+        Ok(tile)
+    }
+
+    #[test]
+    fn test_explore_maze() {
+        let mut pos = (0, 0);
+        let map = explore_with(|dir| maze_step(&mut pos, dir)).unwrap();
+
+        assert_eq!(pos, (0, 0));
+        assert_eq!(map.target(), Some((1, -2)));
+        assert_eq!(map.tiles().values().filter(|t| **t != Tile::Wall).count(), 8);
+
+        let path = map.shortest_path((0, 0), (1, -2)).unwrap();
+        assert_eq!(path, vec![Direction::Down, Direction::Down, Direction::Right]);
+        assert_eq!(map.flood_fill((1, -2)), 4);
+        assert_eq!(map.shortest_path((0, 0), (9, 9)), None);
+    }
+
+    #[test]
+    fn test_droid() {
+        // Replies "wall" for north and "found" for anything else
+        let ints = vec![3, 100, 1008, 100, 1, 101, 1005, 101, 14, 104, 2, 1105, 1, 0, 104, 0, 1105, 1, 0];
+        let mut droid = Droid::new(&ints);
+
+        assert_eq!(droid.step(Direction::Up).unwrap(), Tile::Wall);
+        assert_eq!(droid.step(Direction::Left).unwrap(), Tile::Target);
+        assert!(Droid::new(&[3, 0, 104, 7, 99]).step(Direction::Up).is_err());
+    }
+
+    #[test]
+    fn test_droid_status_count() {
+        // Answers the first move, then reads the next one without replying
+        let silent = vec![3, 100, 104, 1, 3, 100, 3, 100, 99];
+        let mut droid = Droid::new(&silent);
+
+        assert_eq!(droid.step(Direction::Up).unwrap(), Tile::Open);
+        match droid.step(Direction::Up) {
+            Err(ExploreErr::StatusCount(0)) => (),
+            res => panic!("Expected a missing status, got {:?}", res),
+        }
+
+        // Replies twice to every move
+        let chatty = vec![3, 100, 104, 1, 104, 1, 1105, 1, 0];
+        match Droid::new(&chatty).step(Direction::Up) {
+            Err(ExploreErr::StatusCount(2)) => (),
+            res => panic!("Expected two statuses, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_failed_backtrack() {
+        // Moving east works once, after that everything is a wall
+        let mut moved = false;
+        let res = explore_with(|dir| {
+            if dir == Direction::Right && !moved {
+                moved = true;
+                Ok(Tile::Open)
+            } else {
+                Ok(Tile::Wall)
+            }
+        });
+
+        match res {
+            Err(ExploreErr::Backtrack(Tile::Wall)) => (),
+            res => panic!("Expected the move back to fail, got {:?}", res),
+        }
+    }
+}
@@ -25,11 +25,12 @@ impl Instruction for Add {
             Mode::Immediate => { return Err(ProgramErr::NeverImmediate); },
             Mode::Relative => program.rel_base() + self.target.1,
         };
+        let next = program.pointer + 4;
 
         Ok(
             program
-                .set_ints(target, left_val + right_val)
-                .set_pointer(program.pointer + 4)
+                .write_int(target, left_val + right_val)
+                .jump(next)
         )
     }
 
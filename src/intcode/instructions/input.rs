@@ -14,11 +14,12 @@ impl Instruction for Input {
             Mode::Immediate => { return Err(ProgramErr::NeverImmediate); },
             Mode::Relative => program.rel_base() + self.target.1,
         };
+        let next = program.pointer + 2;
 
         Ok(program
-            .set_ints(target, val)
+            .write_int(target, val)
             .consume_input()
-            .set_pointer(program.pointer + 2))
+            .jump(next))
     }
 
     fn test(val: i64) -> bool {
@@ -19,11 +19,9 @@ impl Instruction for JmpIfFalse {
             Mode::Relative => program.get_rel_int(self.target.1),
         };
 
-        if val == 0 {
-            Ok(program.set_pointer(target))
-        } else {
-            Ok(program.set_pointer(program.pointer + 3))
-        }
+        let next = if val == 0 { target } else { program.pointer + 3 };
+
+        Ok(program.jump(next))
     }
 
     fn test(val: i64) -> bool {
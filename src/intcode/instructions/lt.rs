@@ -24,18 +24,19 @@ impl Instruction for LessThan {
             Mode::Immediate => { return Err(ProgramErr::NeverImmediate); },
             Mode::Relative => program.rel_base() + self.target.1,
         };
+        let next = program.pointer + 4;
 
         if left_val < right_val {
             Ok(
                 program
-                    .set_ints(target, 1)
-                    .set_pointer(program.pointer + 4)
+                    .write_int(target, 1)
+                    .jump(next)
             )
         } else {
             Ok(
                 program
-                    .set_ints(target, 0)
-                    .set_pointer(program.pointer + 4)
+                    .write_int(target, 0)
+                    .jump(next)
             )
         }
     }
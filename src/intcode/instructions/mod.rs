@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub mod add;
 pub mod mul;
@@ -11,9 +12,9 @@ pub mod eq;
 pub mod exit;
 pub mod offset;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Program {
-    ints: HashMap<i64, i64>,
+    ints: Arc<HashMap<i64, i64>>,
     pointer: i64,
     inputs: Vec<i64>,
     outputs: Vec<i64>,
@@ -73,15 +74,17 @@ impl Program {
             h_ints.insert(i as i64, *v);
         }
 
-        Program { ints: h_ints, pointer, outputs, inputs, has_exited: false, rel_base }
+        Program { ints: Arc::new(h_ints), pointer, outputs, inputs, has_exited: false, rel_base }
     }
 
     pub fn new_h(ints: HashMap<i64, i64>, pointer: i64, outputs: Vec<i64>, inputs: Vec<i64>, rel_base: i64) -> Program {
-        Program { ints, pointer, outputs, inputs, has_exited: false, rel_base }
+        Program { ints: Arc::new(ints), pointer, outputs, inputs, has_exited: false, rel_base }
     }
 
-    fn exit(&self) -> Program {
-        Program { ints: self.ints.clone(), pointer: self.pointer, outputs: self.outputs.clone(), inputs: self.inputs.clone(), has_exited: true, rel_base: self.rel_base }
+    pub(crate) fn exit(mut self) -> Program {
+        self.has_exited = true;
+
+        self
     }
 
     pub fn has_exited(&self) -> bool {
@@ -100,42 +103,59 @@ impl Program {
     }
 
     pub fn ints(&self) -> HashMap<i64, i64> {
-        self.ints.clone()
+        (*self.ints).clone()
     }
 
     pub fn outputs(&self) -> Vec<i64> { self.outputs.clone() }
 
+    pub fn output_count(&self) -> usize { self.outputs.len() }
+
+    /// Outputs from index `start` on, borrowed instead of copied
+    pub fn outputs_since(&self, start: usize) -> &[i64] {
+        self.outputs.get(start..).unwrap_or(&[])
+    }
+
     pub fn inputs(&self) -> Vec<i64> { self.inputs.clone() }
 
-    pub fn push_input(&self, val: i64) -> Program {
-        let mut inputs = self.inputs.clone();
-        inputs.push(val);
+    pub fn push_input(mut self, val: i64) -> Program {
+        self.inputs.push(val);
+        self.has_exited = false;
 
-        Program { ints: self.ints.clone(), pointer: self.pointer, outputs: self.outputs.clone(), inputs, has_exited: false, rel_base: self.rel_base }
+        self
     }
 
-    pub fn consume_input(&self) -> Program {
-        let mut less_inputs = vec![];
-        for (i, val) in self.inputs.iter().enumerate() {
-            if i > 0 {
-                less_inputs.push(*val);
-            }
+    pub fn consume_input(mut self) -> Program {
+        if !self.inputs.is_empty() {
+            self.inputs.remove(0);
         }
+        self.has_exited = false;
 
-        Program { ints: self.ints.clone(), pointer: self.pointer, outputs: self.outputs.clone(), inputs: less_inputs, has_exited: false, rel_base: self.rel_base }
+        self
     }
 
     pub fn pointer(&self) -> i64 { self.pointer }
 
     pub fn set_ints(&self, index: i64, val: i64) -> Program {
-        let mut ints: HashMap<i64, i64> = self.ints.clone();
+        let mut ints: HashMap<i64, i64> = (*self.ints).clone();
         ints.insert(index, val);
 
         Program::new_h(ints, self.pointer, self.outputs.clone(), self.inputs.clone(), self.rel_base)
     }
 
-    fn set_rel_base(&self, rel_base: i64) -> Program {
-        Program { ints: self.ints.clone(), pointer: self.pointer, outputs: self.outputs.clone(), inputs: self.inputs.clone(), has_exited: false, rel_base }
+    /// Like `set_ints`, but takes ownership so the memory is only copied if
+    /// another `Program` still shares it
+    pub(crate) fn write_int(mut self, index: i64, val: i64) -> Program {
+        Arc::make_mut(&mut self.ints).insert(index, val);
+        self.has_exited = false;
+
+        self
+    }
+
+    fn set_rel_base(mut self, rel_base: i64) -> Program {
+        self.rel_base = rel_base;
+        self.has_exited = false;
+
+        self
     }
 
     pub fn rel_base(&self) -> i64 { self.rel_base }
@@ -144,15 +164,23 @@ impl Program {
         Program { ints: self.ints.clone(), pointer, outputs: self.outputs.clone(), inputs: self.inputs.clone(), has_exited: false, rel_base: self.rel_base }
     }
 
+    /// Like `set_pointer`, but takes ownership so nothing is copied
+    pub(crate) fn jump(mut self, pointer: i64) -> Program {
+        self.pointer = pointer;
+        self.has_exited = false;
+
+        self
+    }
+
     pub fn get_pointer(&self) -> i64 {
         self.pointer
     }
 
-    fn push_output(&self, output: i64) -> Program {
-        let mut outputs = self.outputs.clone();
-        outputs.push(output);
+    fn push_output(mut self, output: i64) -> Program {
+        self.outputs.push(output);
+        self.has_exited = false;
 
-        Program { ints: self.ints.clone(), pointer: self.pointer, outputs, inputs: self.inputs.clone(), has_exited: false, rel_base: self.rel_base }
+        self
     }
 
     pub fn get_int(&self, index: i64) -> i64 {
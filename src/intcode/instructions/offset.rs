@@ -13,10 +13,12 @@ impl Instruction for RelBaseOffset {
             Mode::Relative => program.get_rel_int(self.diff.1),
         };
 
+        let (rel_base, next) = (program.rel_base() + diff, program.pointer + 2);
+
         Ok(
             program
-                .set_rel_base(program.rel_base() + diff)
-                .set_pointer(program.pointer + 2)
+                .set_rel_base(rel_base)
+                .jump(next)
         )
     }
 
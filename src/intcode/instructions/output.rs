@@ -13,10 +13,12 @@ impl Instruction for Output {
             Mode::Relative => program.get_rel_int(self.val.1),
         };
 
+        let next = program.pointer + 2;
+
         Ok(
             program
                 .push_output(val)
-                .set_pointer(program.pointer + 2)
+                .jump(next)
         )
     }
 
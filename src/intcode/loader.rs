@@ -0,0 +1,117 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Fail)]
+pub enum LoadErr {
+    #[fail(display = "Invalid Intcode token {:?} at index {} (line {}, column {})", token, index, line, column)]
+    InvalidToken { token: String, index: usize, line: usize, column: usize },
+    #[fail(display = "Couldn't read Intcode program: {}", _0)]
+    Io(#[cause] std::io::Error),
+}
+
+impl From<std::io::Error> for LoadErr {
+    fn from(err: std::io::Error) -> LoadErr {
+        LoadErr::Io(err)
+    }
+}
+
+/// Parses a comma separated Intcode program, ignoring surrounding whitespace
+/// and a trailing comma
+///
+/// ```
+/// use aoc_2019::intcode::loader::{load_str, LoadErr};
+///
+/// assert_eq!(load_str("1,0,0,0,99").unwrap(), vec![1, 0, 0, 0, 99]);
+/// assert_eq!(load_str(" 1, -2,\n3 ,\n").unwrap(), vec![1, -2, 3]);
+///
+/// match load_str("1,0,\nx,99") {
+///     Err(LoadErr::InvalidToken { token, index, line, column }) => {
+///         assert_eq!((&token[..], index, line, column), ("x", 2, 2, 1));
+///     },
+///     _ => panic!("Expected an invalid token"),
+/// }
+/// ```
+pub fn load_str(input: &str) -> Result<Vec<i64>, LoadErr> {
+    let mut ints = vec![];
+    let mut offset = 0;
+    let tokens: Vec<&str> = input.split(',').collect();
+
+    for (index, raw) in tokens.iter().enumerate() {
+        let token = raw.trim();
+        if token.is_empty() && index == tokens.len() - 1 {
+            break;
+        }
+
+        match token.parse::<i64>() {
+            Ok(int) => ints.push(int),
+            Err(_) => {
+                let start = offset + (raw.len() - raw.trim_start().len());
+                let (line, column) = line_and_column(input, start);
+
+                return Err(LoadErr::InvalidToken { token: token.to_string(), index, line, column });
+            }
+        }
+
+        offset += raw.len() + 1;
+    }
+
+    Ok(ints)
+}
+
+pub fn load_reader<R: Read>(mut reader: R) -> Result<Vec<i64>, LoadErr> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    load_str(&input)
+}
+
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<i64>, LoadErr> {
+    load_reader(File::open(path)?)
+}
+
+fn line_and_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_str() {
+        assert_eq!(load_str("").unwrap(), vec![]);
+        assert_eq!(load_str("99").unwrap(), vec![99]);
+        assert_eq!(load_str("1,2,3,\n").unwrap(), vec![1, 2, 3]);
+        assert_eq!(load_str("1,\r\n2,\t3").unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_load_str_invalid() {
+        match load_str("1,,2") {
+            Err(LoadErr::InvalidToken { index, .. }) => assert_eq!(index, 1),
+            _ => panic!("Expected an invalid token"),
+        }
+        match load_str("1,2,3a") {
+            Err(LoadErr::InvalidToken { token, index, line, column }) => {
+                assert_eq!((&token[..], index, line, column), ("3a", 2, 1, 5));
+            },
+            _ => panic!("Expected an invalid token"),
+        }
+    }
+
+    #[test]
+    fn test_load_reader() {
+        assert_eq!(load_reader("104,1,99\n".as_bytes()).unwrap(), vec![104, 1, 99]);
+    }
+
+    #[test]
+    fn test_load_file() {
+        assert_eq!(load_file("input/2019/day2.txt").unwrap()[..4], [1, 0, 0, 3][..]);
+        assert!(load_file("input/2019/missing.txt").is_err());
+    }
+}
@@ -9,8 +9,25 @@ use crate::intcode::instructions::lt::LessThan;
 use crate::intcode::instructions::eq::Equals;
 use crate::intcode::instructions::exit::Exit;
 use crate::intcode::instructions::offset::RelBaseOffset;
+use crate::intcode::cache::{DecodeCache, step_cached};
 
 pub mod instructions;
+pub mod loader;
+pub mod ascii;
+pub mod disasm;
+pub mod diff;
+pub mod search;
+pub mod symbolic;
+pub mod cfg;
+pub mod compiler;
+pub mod cache;
+pub mod snapshot;
+pub mod replay;
+pub mod executor;
+pub mod agent;
+pub mod explorer;
+#[cfg(test)]
+mod conformance;
 
 #[derive(Debug)]
 pub enum InstrType {
@@ -26,6 +43,18 @@ pub enum InstrType {
     Exit(Exit),
 }
 
+impl InstrType {
+    /// Number of ints the instruction occupies, opcode included
+    pub fn size(&self) -> i64 {
+        match self {
+            InstrType::Add(_) | InstrType::Mul(_) | InstrType::LessThan(_) | InstrType::Equals(_) => 4,
+            InstrType::JmpIfFalse(_) | InstrType::JmpIfTrue(_) => 3,
+            InstrType::Input(_) | InstrType::Output(_) | InstrType::RelBaseOffset(_) => 2,
+            InstrType::Exit(_) => 1,
+        }
+    }
+}
+
 pub fn get_instruction(program: &Program) -> Result<InstrType, ProgramErr> {
     let code = *program.peek().ok_or(ProgramErr::Missing { i: program.get_pointer() })?;
 
@@ -55,22 +84,64 @@ pub fn get_instruction(program: &Program) -> Result<InstrType, ProgramErr> {
 }
 
 
-pub fn run_program(program_ints: &Vec<i64>, inputs: &Vec<i64>) -> Result<Program, ProgramErr> {
-    let mut program = Program::new(program_ints.clone(), 0, vec![], inputs.clone(), 0);
+pub fn execute(instruction: &InstrType, program: Program) -> Result<Program, ProgramErr> {
+    match instruction {
+        InstrType::Add(instr) => instr.run(program),
+        InstrType::Mul(instr) => instr.run(program),
+        InstrType::Input(instr) => instr.run(program),
+        InstrType::Output(instr) => instr.run(program),
+        InstrType::JmpIfFalse(instr) => instr.run(program),
+        InstrType::JmpIfTrue(instr) => instr.run(program),
+        InstrType::LessThan(instr) => instr.run(program),
+        InstrType::Equals(instr) => instr.run(program),
+        InstrType::Exit(instr) => instr.run(program),
+        InstrType::RelBaseOffset(instr) => instr.run(program),
+    }
+}
+
+pub fn step(program: Program) -> Result<Program, ProgramErr> {
+    let instruction = get_instruction(&program)?;
+
+    execute(&instruction, program)
+}
+
+/// True when the next instruction reads input but the input queue is empty
+pub fn awaits_input(program: &Program) -> Result<bool, ProgramErr> {
+    if program.has_exited() || !program.inputs().is_empty() {
+        return Ok(false);
+    }
+
+    match get_instruction(program)? {
+        InstrType::Input(_) => Ok(true),
+        _ => Ok(false),
+    }
+}
+
+/// Like `awaits_input`, but only looks at the opcode instead of decoding the
+/// whole instruction
+fn blocked(program: &Program) -> bool {
+    program.inputs().is_empty() && program.peek().map(|code| Input::test(*code)).unwrap_or(false)
+}
+
+/// Runs the program until it halts or blocks on an empty input queue
+pub fn run_until_blocked(program: Program) -> Result<Program, ProgramErr> {
+    let mut program = program;
+    let mut cache = DecodeCache::new();
+
+    while !program.has_exited() && !blocked(&program) {
+        program = step_cached(program, &mut cache)?;
+    }
+
+    Ok(program)
+}
+
+/// Runs an already constructed program until it halts
+pub fn run_to_exit(program: Program) -> Result<Program, ProgramErr> {
+    let mut program = program;
+    let mut cache = DecodeCache::new();
 
     loop {
-        program = match get_instruction(&program)? {
-            InstrType::Add(instr) => instr.run(program)?,
-            InstrType::Mul(instr) => instr.run(program)?,
-            InstrType::Input(instr) => instr.run(program)?,
-            InstrType::Output(instr) => instr.run(program)?,
-            InstrType::JmpIfFalse(instr) => instr.run(program)?,
-            InstrType::JmpIfTrue(instr) => instr.run(program)?,
-            InstrType::LessThan(instr) => instr.run(program)?,
-            InstrType::Equals(instr) => instr.run(program)?,
-            InstrType::Exit(instr) => instr.run(program)?,
-            InstrType::RelBaseOffset(instr) => instr.run(program)?,
-        };
+        program = step_cached(program, &mut cache)?;
 
         if program.has_exited() {
             return Ok(program)
@@ -78,6 +149,10 @@ pub fn run_program(program_ints: &Vec<i64>, inputs: &Vec<i64>) -> Result<Program
     }
 }
 
+pub fn run_program(program_ints: &[i64], inputs: &[i64]) -> Result<Program, ProgramErr> {
+    run_to_exit(Program::new(program_ints.to_vec(), 0, vec![], inputs.to_vec(), 0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,43 +160,43 @@ mod tests {
     #[test]
     fn test_run_program() {
         assert_eq!(
-            run_program(&vec![1,0,0,0,99], &vec![]).unwrap().set_pointer(0).get_ints(5).unwrap()[..],
+            run_program(&[1,0,0,0,99], &[]).unwrap().set_pointer(0).get_ints(5).unwrap()[..],
             vec![2,0,0,0,99][..]
         );
         assert_eq!(
-            run_program(&vec![2,3,0,3,99], &vec![]).unwrap().set_pointer(0).get_ints(5).unwrap()[..],
+            run_program(&[2,3,0,3,99], &[]).unwrap().set_pointer(0).get_ints(5).unwrap()[..],
             vec![2,3,0,6,99][..]
         );
         assert_eq!(
-            run_program(&vec![2,4,4,5,99,0], &vec![]).unwrap().set_pointer(0).get_ints(6).unwrap()[..],
+            run_program(&[2,4,4,5,99,0], &[]).unwrap().set_pointer(0).get_ints(6).unwrap()[..],
             vec![2,4,4,5,99,9801][..]
         );
         assert_eq!(
-            run_program(&vec![1,1,1,4,99,5,6,0,99], &vec![]).unwrap().set_pointer(0).get_ints(9).unwrap()[..],
+            run_program(&[1,1,1,4,99,5,6,0,99], &[]).unwrap().set_pointer(0).get_ints(9).unwrap()[..],
             vec![30,1,1,4,2,5,6,0,99][..]
         );
         assert_eq!(
-            run_program(&vec![3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], &vec![999]).unwrap().outputs()[..],
+            run_program(&[3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], &[999]).unwrap().outputs()[..],
             vec![1][..]
         );
         assert_eq!(
-            run_program(&vec![3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], &vec![0]).unwrap().outputs()[..],
+            run_program(&[3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], &[0]).unwrap().outputs()[..],
             vec![0][..]
         );
         assert_eq!(
-            run_program(&vec![3,3,1105,-1,9,1101,0,0,12,4,12,99,1], &vec![999]).unwrap().outputs()[..],
+            run_program(&[3,3,1105,-1,9,1101,0,0,12,4,12,99,1], &[999]).unwrap().outputs()[..],
             vec![1][..]
         );
         assert_eq!(
-            run_program(&vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &vec![5]).unwrap().outputs()[..],
+            run_program(&[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &[5]).unwrap().outputs()[..],
             vec![999][..]
         );
         assert_eq!(
-            run_program(&vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &vec![8]).unwrap().outputs()[..],
+            run_program(&[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &[8]).unwrap().outputs()[..],
             vec![1000][..]
         );
         assert_eq!(
-            run_program(&vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &vec![10]).unwrap().outputs()[..],
+            run_program(&[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &[10]).unwrap().outputs()[..],
             vec![1001][..]
         );
     }
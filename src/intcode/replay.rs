@@ -0,0 +1,154 @@
+use intcode::{get_instruction, step, run_to_exit, InstrType};
+use intcode::instructions::{Program, ProgramErr};
+use intcode::loader::{load_str, load_file, LoadErr};
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Every input a machine consumed, in order. Intcode is deterministic, so
+/// queueing the same inputs up front reproduces the run exactly, however they
+/// were supplied the first time around.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayLog {
+    inputs: Vec<i64>,
+}
+
+impl ReplayLog {
+    pub fn new() -> ReplayLog {
+        ReplayLog::default()
+    }
+
+    pub fn inputs(&self) -> &[i64] { &self.inputs }
+
+    pub fn len(&self) -> usize { self.inputs.len() }
+
+    pub fn is_empty(&self) -> bool { self.inputs.is_empty() }
+
+    /// Records the input the program is about to read. Call this right before
+    /// executing an input instruction.
+    pub fn record_input(&mut self, program: &Program) {
+        if let Some(val) = program.inputs().first() {
+            self.inputs.push(*val);
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), LoadErr> {
+        writeln!(File::create(path)?, "{}", self)?;
+
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ReplayLog, LoadErr> {
+        Ok(ReplayLog { inputs: load_file(path)? })
+    }
+}
+
+impl fmt::Display for ReplayLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inputs: Vec<String> = self.inputs.iter().map(|i| i.to_string()).collect();
+
+        write!(f, "{}", inputs.join(","))
+    }
+}
+
+impl std::str::FromStr for ReplayLog {
+    type Err = LoadErr;
+
+    fn from_str(s: &str) -> Result<ReplayLog, LoadErr> {
+        Ok(ReplayLog { inputs: load_str(s)? })
+    }
+}
+
+/// Executes one instruction like `step`, recording the input it consumes
+pub fn step_recorded(program: Program, log: &mut ReplayLog) -> Result<Program, ProgramErr> {
+    if let InstrType::Input(_) = get_instruction(&program)? {
+        log.record_input(&program);
+    }
+
+    step(program)
+}
+
+/// Runs the program from the start with the recorded inputs queued
+///
+/// ```
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::run_until_blocked;
+/// use aoc_2019::intcode::replay::{ReplayLog, step_recorded, replay};
+///
+/// // Outputs the running sum of its inputs until it reads a 0
+/// let ints = vec![3, 100, 1, 100, 101, 101, 4, 101, 1005, 100, 0, 99];
+/// let mut log = ReplayLog::new();
+/// let mut program = Program::new(ints.clone(), 0, vec![], vec![], 0);
+///
+/// for input in &[4, 6, 0] {
+///     program = program.push_input(*input);
+///     while !program.inputs().is_empty() {
+///         program = step_recorded(program, &mut log).unwrap();
+///     }
+///     program = run_until_blocked(program).unwrap();
+/// }
+///
+/// assert_eq!(log.to_string(), "4,6,0");
+/// assert_eq!(replay(&ints, &log.to_string().parse().unwrap()).unwrap(), program);
+/// ```
+pub fn replay(ints: &[i64], log: &ReplayLog) -> Result<Program, ProgramErr> {
+    run_to_exit(Program::new(ints.to_vec(), 0, vec![], log.inputs.clone(), 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use day11::{self, Color};
+    use intcode::loader::load_file;
+    use std::collections::HashSet;
+    use std::env;
+
+    #[test]
+    fn test_record_only_consumed() {
+        let mut log = ReplayLog::new();
+        let mut program = Program::new(vec![3, 0, 4, 0, 99], 0, vec![], vec![7, 8], 0);
+
+        while !program.has_exited() {
+            program = step_recorded(program, &mut log).unwrap();
+        }
+
+        assert_eq!(log.inputs(), &[7]);
+        assert_eq!(program.inputs(), vec![8]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let log: ReplayLog = "1,-2,3".parse().unwrap();
+        let path = env::temp_dir().join(format!("aoc_2019_replay_{}.txt", std::process::id()));
+
+        log.save(&path).unwrap();
+        let loaded = ReplayLog::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, log);
+        assert!("".parse::<ReplayLog>().unwrap().is_empty());
+        assert!("1,x".parse::<ReplayLog>().is_err());
+    }
+
+    #[test]
+    fn test_replay_day11() {
+        let ints = load_file("input/2019/day11.txt").unwrap();
+        let mut log = ReplayLog::new();
+        let ship = day11::run_recorded(&ints, Color::Black, &mut log).unwrap();
+        let outputs = replay(&ints, &log).unwrap().outputs();
+
+        assert_eq!(outputs.len(), 2 * log.len());
+
+        // Walk the robot again using only the replayed outputs
+        let (mut pos, mut dir) = ((0, 0), (0, -1));
+        let mut painted = HashSet::new();
+        for pair in outputs.chunks(2) {
+            painted.insert(pos);
+            dir = if pair[1] == 0 { (dir.1, -dir.0) } else { (-dir.1, dir.0) };
+            pos = (pos.0 + dir.0, pos.1 + dir.1);
+        }
+
+        assert_eq!(painted.len(), ship.get_painted_panel_cnt());
+    }
+}
@@ -0,0 +1,155 @@
+use intcode::run_to_exit;
+use intcode::cache::{DecodeCache, step_cached};
+use intcode::instructions::{Program, ProgramErr};
+use rayon::prelude::*;
+use std::ops::RangeInclusive;
+
+/// Writes each `(address, value)` pair into memory and runs the program until it halts
+pub fn run_patched(program: &Program, patches: &[(i64, i64)]) -> Result<Program, ProgramErr> {
+    let patched = patches
+        .iter()
+        .fold(program.clone(), |p, (addr, val)| p.set_ints(*addr, *val));
+
+    run_to_exit(patched)
+}
+
+/// Candidates handed to the thread pool at a time
+const CHUNK: usize = 1024;
+
+/// Every combination of `values` for a number of cells, in lexicographic
+/// order. Each one is made from the previous by turning the last cell like an
+/// odometer wheel, carrying into the cells before it.
+struct Combinations {
+    values: RangeInclusive<i64>,
+    next: Option<Vec<i64>>,
+}
+
+impl Iterator for Combinations {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Vec<i64>> {
+        let current = self.next.take()?;
+        let mut following = current.clone();
+
+        for i in (0..following.len()).rev() {
+            if following[i] < *self.values.end() {
+                following[i] += 1;
+                self.next = Some(following);
+                break;
+            }
+            following[i] = *self.values.start();
+        }
+
+        Some(current)
+    }
+}
+
+fn combinations(cnt: usize, values: &RangeInclusive<i64>) -> Combinations {
+    let next = if cnt == 0 || !values.is_empty() { Some(vec![*values.start(); cnt]) } else { None };
+
+    Combinations { values: values.clone(), next }
+}
+
+/// Runs until the program halts, or `None` if it crashes or is still running
+/// after `max_steps` instructions
+fn run_limited(program: Program, max_steps: usize) -> Option<Program> {
+    let mut program = program;
+    let mut cache = DecodeCache::new();
+
+    for _ in 0..max_steps {
+        if program.has_exited() {
+            return Some(program);
+        }
+        program = step_cached(program, &mut cache).ok()?;
+    }
+
+    if program.has_exited() { Some(program) } else { None }
+}
+
+/// Tries every combination of `values` in the cells at `addrs` in parallel and
+/// returns the first one (in lexicographic order) that leaves `target` at
+/// `output_addr` once the program halts. Candidates that crash the program,
+/// or don't halt within `max_steps` instructions, count as misses.
+///
+/// ```
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::search::find_patch;
+///
+/// // [0] = [9] * [10]
+/// let program = Program::new(vec![2, 9, 10, 0, 99, 0, 0, 0, 0, 0, 0], 0, vec![], vec![], 0);
+///
+/// assert_eq!(find_patch(&program, &[9, 10], 0..=9, 0, 12, 100), Some(vec![2, 6]));
+/// assert_eq!(find_patch(&program, &[9, 10], 0..=9, 0, 13, 100), None);
+/// ```
+pub fn find_patch(program: &Program, addrs: &[i64], values: RangeInclusive<i64>, output_addr: i64, target: i64, max_steps: usize) -> Option<Vec<i64>> {
+    let mut candidates = combinations(addrs.len(), &values);
+
+    loop {
+        let chunk: Vec<Vec<i64>> = candidates.by_ref().take(CHUNK).collect();
+        if chunk.is_empty() {
+            return None;
+        }
+
+        let found = chunk.into_par_iter().find_first(|candidate| {
+            let patched = addrs.iter()
+                .zip(candidate.iter())
+                .fold(program.clone(), |p, (addr, val)| p.set_ints(*addr, *val));
+
+            run_limited(patched, max_steps)
+                .map(|res| res.get_int(output_addr) == target)
+                .unwrap_or(false)
+        });
+
+        if found.is_some() {
+            return found;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(combinations(0, &(0..=2)).collect::<Vec<_>>(), vec![Vec::<i64>::new()]);
+        assert_eq!(combinations(2, &(0..=1)).collect::<Vec<_>>(), vec![vec![0, 0], vec![0, 1], vec![1, 0], vec![1, 1]]);
+        assert_eq!(combinations(2, &RangeInclusive::new(3, 2)).count(), 0);
+        assert_eq!(combinations(3, &(0..=99)).nth(123_456), Some(vec![12, 34, 56]));
+        assert_eq!(combinations(3, &(0..=99)).count(), 1_000_000);
+    }
+
+    #[test]
+    fn test_run_patched() {
+        let program = Program::new(vec![1, 0, 0, 0, 99], 0, vec![], vec![], 0);
+        let res = run_patched(&program, &[(1, 4), (2, 4)]).unwrap();
+
+        assert_eq!(res.get_int(0), 198);
+        assert!(res.has_exited());
+    }
+
+    #[test]
+    fn test_find_patch_skips_crashing_candidates() {
+        // Patching [1] to anything but 0 jumps into an invalid opcode
+        let program = Program::new(vec![1105, 0, 8, 1101, 1, 1, 0, 99, 0], 0, vec![], vec![], 0);
+
+        assert_eq!(find_patch(&program, &[1], 0..=99, 0, 2, 100), Some(vec![0]));
+    }
+
+    #[test]
+    fn test_find_patch_skips_looping_candidates() {
+        // [0] = [10] + 1, but loops forever unless [9] is 0
+        let program = Program::new(vec![1005, 9, 0, 1001, 10, 1, 0, 99, 0, 0, 0], 0, vec![], vec![], 0);
+
+        assert_eq!(find_patch(&program, &[9, 10], 0..=3, 0, 3, 1000), Some(vec![0, 2]));
+        assert_eq!(find_patch(&program, &[9, 10], 0..=3, 0, 5, 1000), None);
+    }
+
+    #[test]
+    fn test_find_patch_across_chunks() {
+        // [0] = [9] * [10], with the answer far past the first chunk
+        let program = Program::new(vec![2, 9, 10, 0, 99, 0, 0, 0, 0, 0, 0], 0, vec![], vec![], 0);
+
+        assert_eq!(find_patch(&program, &[9, 10], 0..=99, 0, 99 * 98, 100), Some(vec![98, 99]));
+    }
+}
@@ -0,0 +1,269 @@
+use intcode::instructions::Program;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+const HEADER: &str = "intcode-snapshot 1";
+
+#[derive(Debug, Fail)]
+pub enum SnapshotErr {
+    #[fail(display = "Not an Intcode snapshot, expected {:?} on the first line", _0)]
+    MissingHeader(String),
+    #[fail(display = "Invalid snapshot line {}: {:?}", line, content)]
+    InvalidLine { line: usize, content: String },
+    #[fail(display = "Snapshot is missing the {:?} field", _0)]
+    MissingField(String),
+    #[fail(display = "Snapshot line {} repeats the {:?} field", line, name)]
+    DuplicateField { line: usize, name: String },
+    #[fail(display = "Snapshot line {} sets address {} a second time", line, addr)]
+    DuplicateAddress { line: usize, addr: i64 },
+    #[fail(display = "Couldn't access snapshot: {}", _0)]
+    Io(#[cause] std::io::Error),
+}
+
+impl From<std::io::Error> for SnapshotErr {
+    fn from(err: std::io::Error) -> SnapshotErr {
+        SnapshotErr::Io(err)
+    }
+}
+
+fn join(ints: &[i64]) -> String {
+    ints.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(",")
+}
+
+/// Serializes the complete machine state. Memory is written as runs of
+/// contiguous addresses, so sparse writes far past the program stay small.
+///
+/// ```
+/// use aoc_2019::intcode::run_until_blocked;
+/// use aoc_2019::intcode::instructions::Program;
+/// use aoc_2019::intcode::snapshot::{to_snapshot, from_snapshot};
+///
+/// // Echoes inputs until it reads a 0
+/// let program = Program::new(vec![3, 100, 4, 100, 1005, 100, 0, 99], 0, vec![], vec![5], 0);
+/// let paused = run_until_blocked(program).unwrap();
+///
+/// let saved = to_snapshot(&paused);
+/// assert!(saved.contains("mem 100 5"));
+///
+/// let resumed = from_snapshot(&saved).unwrap();
+/// assert_eq!(resumed, paused);
+/// assert_eq!(run_until_blocked(resumed.push_input(0)).unwrap().outputs(), vec![5, 0]);
+/// ```
+pub fn to_snapshot(program: &Program) -> String {
+    let ints = program.ints();
+    let mut addrs: Vec<&i64> = ints.keys().collect();
+    addrs.sort();
+
+    let mut out = format!(
+        "{}\npointer {}\nrel_base {}\nhalted {}\ninputs {}\noutputs {}\n",
+        HEADER,
+        program.pointer(),
+        program.rel_base(),
+        program.has_exited() as i64,
+        join(&program.inputs()),
+        join(&program.outputs()),
+    );
+
+    let mut i = 0;
+    while i < addrs.len() {
+        let start = *addrs[i];
+        let mut run = vec![];
+
+        while i < addrs.len() && *addrs[i] == start + run.len() as i64 {
+            run.push(ints[addrs[i]]);
+            i += 1;
+        }
+
+        out.push_str(&format!("mem {} {}\n", start, join(&run)));
+    }
+
+    out
+}
+
+pub fn from_snapshot(input: &str) -> Result<Program, SnapshotErr> {
+    let mut lines = input.lines().enumerate();
+
+    match lines.next() {
+        Some((_, header)) if header.trim() == HEADER => (),
+        _ => return Err(SnapshotErr::MissingHeader(HEADER.to_string())),
+    }
+
+    let mut fields: HashMap<&str, Vec<i64>> = HashMap::new();
+    let mut ints: HashMap<i64, i64> = HashMap::new();
+
+    for (i, content) in lines {
+        let invalid = || SnapshotErr::InvalidLine { line: i + 1, content: content.to_string() };
+        let line = content.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, ' ');
+        let key = parts.next().unwrap_or("");
+        let rest = parts.next().unwrap_or("").trim();
+
+        if key == "mem" {
+            let mut mem = rest.splitn(2, ' ');
+            let start: i64 = mem.next().unwrap_or("").parse().map_err(|_| invalid())?;
+            let values = parse_list(mem.next().unwrap_or("")).ok_or_else(invalid)?;
+
+            for (offset, val) in values.iter().enumerate() {
+                let addr = start + offset as i64;
+
+                if ints.insert(addr, *val).is_some() {
+                    return Err(SnapshotErr::DuplicateAddress { line: i + 1, addr });
+                }
+            }
+        } else if ["pointer", "rel_base", "halted", "inputs", "outputs"].contains(&key) {
+            let values = parse_list(rest).ok_or_else(invalid)?;
+            let valid = match key {
+                "inputs" | "outputs" => true,
+                "halted" => values == [0] || values == [1],
+                _ => values.len() == 1,
+            };
+
+            if !valid {
+                return Err(invalid());
+            }
+            if fields.insert(key, values).is_some() {
+                return Err(SnapshotErr::DuplicateField { line: i + 1, name: key.to_string() });
+            }
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    let mut field = |name: &str| fields.remove(name).ok_or_else(|| SnapshotErr::MissingField(name.to_string()));
+    // Scalar fields were checked to hold exactly one value while parsing
+    let scalar = |values: Vec<i64>| values[0];
+
+    let pointer = scalar(field("pointer")?);
+    let rel_base = scalar(field("rel_base")?);
+    let halted = scalar(field("halted")?) != 0;
+    let inputs = field("inputs")?;
+    let outputs = field("outputs")?;
+
+    let program = Program::new_h(ints, pointer, outputs, inputs, rel_base);
+
+    Ok(if halted { program.exit() } else { program })
+}
+
+fn parse_list(input: &str) -> Option<Vec<i64>> {
+    if input.trim().is_empty() {
+        return Some(vec![]);
+    }
+
+    input.split(',').map(|v| v.trim().parse().ok()).collect()
+}
+
+pub fn save<P: AsRef<Path>>(program: &Program, path: P) -> Result<(), SnapshotErr> {
+    File::create(path)?.write_all(to_snapshot(program).as_bytes())?;
+
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Program, SnapshotErr> {
+    let mut input = String::new();
+    File::open(path)?.read_to_string(&mut input)?;
+
+    from_snapshot(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::{run_program, run_until_blocked};
+    use intcode::loader::load_file;
+    use std::env;
+
+    #[test]
+    fn test_round_trip_halted() {
+        let program = run_program(&[109, 19, 204, -34, 21101, 3, 4, 1000, 99], &[]).unwrap();
+        let restored = from_snapshot(&to_snapshot(&program)).unwrap();
+
+        assert!(restored.has_exited());
+        assert_eq!(restored.get_int(1019), 7);
+        assert_eq!(restored, program);
+    }
+
+    #[test]
+    fn test_compact_memory() {
+        let program = Program::new(vec![1, 2, 3], 0, vec![], vec![], 0).set_ints(5000, -4).set_ints(5001, 0);
+
+        assert_eq!(
+            to_snapshot(&program),
+            "intcode-snapshot 1\npointer 0\nrel_base 0\nhalted 0\ninputs \noutputs \nmem 0 1,2,3\nmem 5000 -4,0\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        match from_snapshot("pointer 0") {
+            Err(SnapshotErr::MissingHeader(_)) => (),
+            res => panic!("Expected a missing header, got {:?}", res),
+        }
+        match from_snapshot("intcode-snapshot 1\npointer 0\nmem 0 1,x\n") {
+            Err(SnapshotErr::InvalidLine { line, .. }) => assert_eq!(line, 3),
+            res => panic!("Expected an invalid line, got {:?}", res),
+        }
+        match from_snapshot("intcode-snapshot 1\npointer 0\nrel_base 0\nhalted 0\ninputs\n") {
+            Err(SnapshotErr::MissingField(name)) => assert_eq!(name, "outputs"),
+            res => panic!("Expected a missing field, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_invalid_scalars() {
+        let fields = "rel_base 0\nhalted 0\ninputs\noutputs\n";
+
+        for line in &["pointer", "pointer 1,2,3", "pointer x"] {
+            match from_snapshot(&format!("intcode-snapshot 1\n{}\n{}", line, fields)) {
+                Err(SnapshotErr::InvalidLine { line, .. }) => assert_eq!(line, 2),
+                res => panic!("Expected {:?} to be invalid, got {:?}", line, res),
+            }
+        }
+        match from_snapshot("intcode-snapshot 1\npointer 0\nrel_base 0\nhalted 5\ninputs\noutputs\n") {
+            Err(SnapshotErr::InvalidLine { line, .. }) => assert_eq!(line, 4),
+            res => panic!("Expected an invalid halted flag, got {:?}", res),
+        }
+        assert!(from_snapshot(&format!("intcode-snapshot 1\npointer 0\n{}", fields)).is_ok());
+    }
+
+    #[test]
+    fn test_duplicates() {
+        match from_snapshot("intcode-snapshot 1
+pointer 0
+rel_base 0
+pointer 4
+") {
+            Err(SnapshotErr::DuplicateField { line, name }) => assert_eq!((line, &name[..]), (4, "pointer")),
+            res => panic!("Expected a duplicate field, got {:?}", res),
+        }
+        match from_snapshot("intcode-snapshot 1
+mem 0 1,2,3
+mem 2 9
+") {
+            Err(SnapshotErr::DuplicateAddress { line, addr }) => assert_eq!((line, addr), (3, 2)),
+            res => panic!("Expected a duplicate address, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_save_and_resume_day9() {
+        let ints = load_file("input/2019/day9.txt").unwrap();
+        let paused = run_until_blocked(Program::new(ints.clone(), 0, vec![], vec![], 0)).unwrap();
+        let path = env::temp_dir().join(format!("aoc_2019_snapshot_{}.txt", std::process::id()));
+
+        save(&paused, &path).unwrap();
+        let resumed = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(resumed, paused);
+        assert_eq!(
+            run_until_blocked(resumed.push_input(1)).unwrap().outputs(),
+            run_program(&ints, &[1]).unwrap().outputs()
+        );
+    }
+}
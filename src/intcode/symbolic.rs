@@ -0,0 +1,398 @@
+use intcode::instructions::{Mode, parse_opcode};
+use intcode::disasm::opcode_info;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug, Fail)]
+pub enum SymbolicErr {
+    #[fail(display = "Opcode at {} depends on a symbolic value", addr)]
+    SymbolicOpcode { addr: i64 },
+    #[fail(display = "Branch at {} depends on a symbolic value", addr)]
+    SymbolicBranch { addr: i64 },
+    #[fail(display = "Instruction at {} writes to a symbolic address", addr)]
+    SymbolicAddress { addr: i64 },
+    #[fail(display = "Failed parsing instruction {} at {}", instr, addr)]
+    InvalidInstruction { addr: i64, instr: i64 },
+    #[fail(display = "Expected input, found None")]
+    ExpectedInput,
+    #[fail(display = "Parameters that an instruction writes to will never be in immediate mode")]
+    NeverImmediate,
+    #[fail(display = "Gave up after {} steps", steps)]
+    StepLimit { steps: usize },
+}
+
+/// Sorted variable names, repeated once per power, e.g. `["noun", "noun", "verb"]`
+pub type Monomial = Vec<String>;
+
+/// A polynomial with integer coefficients over named variables
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Poly {
+    terms: BTreeMap<Monomial, i64>,
+}
+
+impl Poly {
+    pub fn constant(c: i64) -> Poly {
+        let mut terms = BTreeMap::new();
+        if c != 0 {
+            terms.insert(vec![], c);
+        }
+
+        Poly { terms }
+    }
+
+    pub fn var(name: &str) -> Poly {
+        let mut terms = BTreeMap::new();
+        terms.insert(vec![name.to_string()], 1);
+
+        Poly { terms }
+    }
+
+    /// Sums up the coefficients of equal monomials, or `None` on overflow
+    fn from_terms(terms: Vec<(Monomial, Option<i64>)>) -> Option<Poly> {
+        let mut out: BTreeMap<Monomial, i64> = BTreeMap::new();
+        for (mut mono, coeff) in terms {
+            mono.sort();
+            let sum = out.entry(mono).or_insert(0);
+            *sum = sum.checked_add(coeff?)?;
+        }
+        out.retain(|_, coeff| *coeff != 0);
+
+        Some(Poly { terms: out })
+    }
+
+    /// `None` if a coefficient overflows
+    pub fn checked_add(&self, other: &Poly) -> Option<Poly> {
+        Poly::from_terms(self.terms.iter().chain(other.terms.iter()).map(|(m, c)| (m.clone(), Some(*c))).collect())
+    }
+
+    /// `None` if a coefficient overflows
+    pub fn checked_mul(&self, other: &Poly) -> Option<Poly> {
+        let mut terms = vec![];
+        for (m1, c1) in &self.terms {
+            for (m2, c2) in &other.terms {
+                terms.push((m1.iter().chain(m2.iter()).cloned().collect(), c1.checked_mul(*c2)));
+            }
+        }
+
+        Poly::from_terms(terms)
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        match self.terms.len() {
+            0 => Some(0),
+            1 => self.terms.get(&vec![]).cloned(),
+            _ => None,
+        }
+    }
+
+    /// The coefficient of the given monomial, e.g. `&["verb"]` or `&[]` for the constant term
+    pub fn coefficient(&self, vars: &[&str]) -> i64 {
+        let mut mono: Monomial = vars.iter().map(|v| v.to_string()).collect();
+        mono.sort();
+
+        *self.terms.get(&mono).unwrap_or(&0)
+    }
+
+    pub fn degree_in(&self, var: &str) -> usize {
+        self.terms
+            .keys()
+            .map(|mono| mono.iter().filter(|v| *v == var).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Replaces `var` with `val`, or `None` if a coefficient overflows
+    pub fn substitute(&self, var: &str, val: i64) -> Option<Poly> {
+        let mut out: BTreeMap<Monomial, i64> = BTreeMap::new();
+
+        for (mono, coeff) in &self.terms {
+            let power = mono.iter().filter(|v| *v == var).count() as u32;
+            let rest: Monomial = mono.iter().filter(|v| *v != var).cloned().collect();
+            let term = val.checked_pow(power)?.checked_mul(*coeff)?;
+
+            let sum = out.entry(rest).or_insert(0);
+            *sum = sum.checked_add(term)?;
+        }
+        out.retain(|_, coeff| *coeff != 0);
+
+        Some(Poly { terms: out })
+    }
+
+    /// Evaluates the polynomial, or `None` if a variable is missing from
+    /// `vals` or the result overflows
+    pub fn eval(&self, vals: &HashMap<&str, i64>) -> Option<i64> {
+        vals.iter()
+            .try_fold(self.clone(), |p, (var, val)| p.substitute(var, *val))?
+            .as_constant()
+    }
+}
+
+impl fmt::Display for Poly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+
+        let mut terms: Vec<(&Monomial, &i64)> = self.terms.iter().collect();
+        terms.sort_by(|(m1, _), (m2, _)| m2.len().cmp(&m1.len()).then(m1.cmp(m2)));
+
+        for (i, (mono, coeff)) in terms.iter().enumerate() {
+            let sign = if **coeff < 0 { "-" } else { "+" };
+            if i > 0 {
+                write!(f, " {} ", sign)?;
+            } else if **coeff < 0 {
+                write!(f, "-")?;
+            }
+
+            let abs = coeff.abs();
+            match (mono.is_empty(), abs) {
+                (true, _) => write!(f, "{}", abs)?,
+                (false, 1) => write!(f, "{}", mono.join("*"))?,
+                (false, _) => write!(f, "{}*{}", abs, mono.join("*"))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A memory cell during symbolic execution, `None` when the value can't be
+/// tracked (e.g. it was read through a symbolic address)
+pub type Value = Option<Poly>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolicProgram {
+    ints: HashMap<i64, Value>,
+    pointer: i64,
+    rel_base: i64,
+    inputs: Vec<i64>,
+    outputs: Vec<Value>,
+    has_exited: bool,
+}
+
+impl SymbolicProgram {
+    pub fn new(ints: &[i64], symbols: &[(i64, &str)], inputs: &[i64]) -> SymbolicProgram {
+        let mut h_ints: HashMap<i64, Value> = ints
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i as i64, Some(Poly::constant(*v))))
+            .collect();
+
+        for (addr, name) in symbols {
+            h_ints.insert(*addr, Some(Poly::var(name)));
+        }
+
+        SymbolicProgram { ints: h_ints, pointer: 0, rel_base: 0, inputs: inputs.to_vec(), outputs: vec![], has_exited: false }
+    }
+
+    pub fn get(&self, addr: i64) -> Value {
+        self.ints.get(&addr).cloned().unwrap_or_else(|| Some(Poly::constant(0)))
+    }
+
+    pub fn outputs(&self) -> &Vec<Value> { &self.outputs }
+
+    pub fn has_exited(&self) -> bool { self.has_exited }
+
+    fn concrete(&self, addr: i64) -> Option<i64> {
+        self.get(addr).and_then(|p| p.as_constant())
+    }
+
+    fn read(&self, mode: &Mode, raw: &Value) -> Value {
+        match mode {
+            Mode::Immediate => raw.clone(),
+            Mode::Parameter => raw.as_ref().and_then(|p| p.as_constant()).and_then(|addr| self.get(addr)),
+            Mode::Relative => raw.as_ref().and_then(|p| p.as_constant()).and_then(|addr| self.get(self.rel_base + addr)),
+        }
+    }
+
+    fn target(&self, mode: &Mode, raw: &Value) -> Result<i64, SymbolicErr> {
+        let addr = raw.as_ref().and_then(|p| p.as_constant());
+
+        match (mode, addr) {
+            (Mode::Immediate, _) => Err(SymbolicErr::NeverImmediate),
+            (_, None) => Err(SymbolicErr::SymbolicAddress { addr: self.pointer }),
+            (Mode::Parameter, Some(addr)) => Ok(addr),
+            (Mode::Relative, Some(addr)) => Ok(self.rel_base + addr),
+        }
+    }
+
+    fn branch_value(&self, val: &Value) -> Result<i64, SymbolicErr> {
+        val.as_ref()
+            .and_then(|p| p.as_constant())
+            .ok_or(SymbolicErr::SymbolicBranch { addr: self.pointer })
+    }
+
+    pub fn step(&mut self) -> Result<(), SymbolicErr> {
+        let addr = self.pointer;
+        let raw = self.concrete(addr).ok_or(SymbolicErr::SymbolicOpcode { addr })?;
+        let op = parse_opcode(raw).map_err(|_| SymbolicErr::InvalidInstruction { addr, instr: raw })?;
+        let (_, cnt) = opcode_info(op.opcode).ok_or(SymbolicErr::InvalidInstruction { addr, instr: raw })?;
+
+        let modes = [op.a, op.b, op.c];
+        let params: Vec<Value> = (1..=cnt as i64).map(|i| self.get(addr + i)).collect();
+        let next = addr + 1 + cnt as i64;
+
+        match op.opcode {
+            1 | 2 | 7 | 8 => {
+                let left = self.read(&modes[0], &params[0]);
+                let right = self.read(&modes[1], &params[1]);
+                let target = self.target(&modes[2], &params[2])?;
+
+                let val = match (op.opcode, left, right) {
+                    (1, Some(l), Some(r)) => l.checked_add(&r),
+                    (2, Some(l), Some(r)) => l.checked_mul(&r),
+                    (7, Some(l), Some(r)) => match (l.as_constant(), r.as_constant()) {
+                        (Some(l), Some(r)) => Some(Poly::constant(if l < r { 1 } else { 0 })),
+                        _ => None,
+                    },
+                    (8, Some(l), Some(r)) => match (l.as_constant(), r.as_constant()) {
+                        (Some(l), Some(r)) => Some(Poly::constant(if l == r { 1 } else { 0 })),
+                        _ if l == r => Some(Poly::constant(1)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+
+                self.ints.insert(target, val);
+                self.pointer = next;
+            },
+            3 => {
+                let target = self.target(&modes[0], &params[0])?;
+                if self.inputs.is_empty() {
+                    return Err(SymbolicErr::ExpectedInput);
+                }
+                let val = self.inputs.remove(0);
+
+                self.ints.insert(target, Some(Poly::constant(val)));
+                self.pointer = next;
+            },
+            4 => {
+                let val = self.read(&modes[0], &params[0]);
+                self.outputs.push(val);
+                self.pointer = next;
+            },
+            5 | 6 => {
+                let val = self.branch_value(&self.read(&modes[0], &params[0]))?;
+                let jump = (op.opcode == 5) == (val != 0);
+
+                self.pointer = if jump {
+                    self.branch_value(&self.read(&modes[1], &params[1]))?
+                } else {
+                    next
+                };
+            },
+            9 => {
+                self.rel_base += self.read(&modes[0], &params[0])
+                    .and_then(|p| p.as_constant())
+                    .ok_or(SymbolicErr::SymbolicAddress { addr })?;
+                self.pointer = next;
+            },
+            _ => {
+                self.has_exited = true;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs the program with the cells in `symbols` replaced by named variables and
+/// returns the final state, so the memory and outputs can be read as
+/// polynomials over those variables. Comparisons on symbolic values produce
+/// untracked values; branching on one is an error.
+///
+/// ```
+/// use aoc_2019::intcode::symbolic::analyze;
+///
+/// // [0] = [9] * 3 + [10]
+/// let res = analyze(&[1002, 9, 3, 0, 1, 0, 10, 0, 99, 0, 0], &[(9, "noun"), (10, "verb")], &[], 100).unwrap();
+///
+/// assert_eq!(res.get(0).unwrap().to_string(), "3*noun + verb");
+/// ```
+pub fn analyze(ints: &[i64], symbols: &[(i64, &str)], inputs: &[i64], max_steps: usize) -> Result<SymbolicProgram, SymbolicErr> {
+    let mut program = SymbolicProgram::new(ints, symbols, inputs);
+
+    for _ in 0..max_steps {
+        program.step()?;
+
+        if program.has_exited() {
+            return Ok(program);
+        }
+    }
+
+    Err(SymbolicErr::StepLimit { steps: max_steps })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poly() {
+        let x = Poly::var("x");
+        let y = Poly::var("y");
+        let p = x.checked_mul(&x).unwrap()
+            .checked_add(&Poly::constant(-2).checked_mul(&y).unwrap()).unwrap()
+            .checked_add(&Poly::constant(7)).unwrap();
+
+        assert_eq!(p.to_string(), "x*x - 2*y + 7");
+        assert_eq!(p.degree_in("x"), 2);
+        assert_eq!(p.coefficient(&["y"]), -2);
+        assert_eq!(p.substitute("x", 3).unwrap().to_string(), "-2*y + 16");
+        assert_eq!(p.substitute("x", i64::MAX), None);
+        assert_eq!(p.substitute("x", 1 << 32), None);
+        assert_eq!(p.as_constant(), None);
+        assert_eq!(p.checked_add(&Poly::constant(-1).checked_mul(&p).unwrap()).unwrap().as_constant(), Some(0));
+        assert_eq!(Poly::constant(i64::MAX).checked_add(&Poly::constant(1)), None);
+        assert_eq!(Poly::constant(1 << 32).checked_mul(&p.checked_mul(&Poly::constant(1 << 32)).unwrap()), None);
+
+        let mut vals = HashMap::new();
+        vals.insert("x", 2);
+        assert_eq!(p.eval(&vals), None);
+        vals.insert("y", 1);
+        assert_eq!(p.eval(&vals), Some(9));
+    }
+
+    #[test]
+    fn test_analyze_output() {
+        // Reads an input into [10], then outputs [9] + [9]
+        let res = analyze(&[3, 10, 1, 9, 9, 11, 4, 11, 99, 0, 0, 0], &[(9, "a")], &[5], 100).unwrap();
+
+        assert_eq!(res.outputs()[..], [Poly::constant(2).checked_mul(&Poly::var("a"))][..]);
+    }
+
+    #[test]
+    fn test_analyze_untracked_read() {
+        // [3] = [[1]] + [[2]], later overwritten with [1] + [2]
+        let res = analyze(&[1, 0, 0, 3, 1, 1, 2, 3, 99], &[(1, "noun"), (2, "verb")], &[], 100).unwrap();
+
+        assert_eq!(res.get(3).unwrap().to_string(), "noun + verb");
+        assert_eq!(res.get(0), Some(Poly::constant(1)));
+    }
+
+    #[test]
+    fn test_analyze_overflow() {
+        // [20] = a * 2^32, then outputs [20] * [20] and [22] + 1 with [22] = i64::MAX
+        let ints = [1002, 20, 1 << 32, 20, 2, 20, 20, 21, 4, 21, 101, 1, 22, 23, 4, 23, 99, 0, 0, 0, 0, 0, i64::MAX, 0];
+        let res = analyze(&ints, &[(20, "a")], &[], 100).unwrap();
+
+        assert_eq!(res.get(20), Poly::constant(1 << 32).checked_mul(&Poly::var("a")));
+        assert_eq!(res.outputs()[..], [None, None][..]);
+    }
+
+    #[test]
+    fn test_analyze_errors() {
+        match analyze(&[1005, 3, 0, 0, 99], &[(3, "x")], &[], 100) {
+            Err(SymbolicErr::SymbolicBranch { addr }) => assert_eq!(addr, 0),
+            res => panic!("Expected a symbolic branch, got {:?}", res),
+        }
+        match analyze(&[1, 0, 0, 5, 99, 0], &[(3, "x")], &[], 100) {
+            Err(SymbolicErr::SymbolicAddress { addr }) => assert_eq!(addr, 0),
+            res => panic!("Expected a symbolic address, got {:?}", res),
+        }
+        match analyze(&[1105, 1, 0], &[], &[], 10) {
+            Err(SymbolicErr::StepLimit { steps }) => assert_eq!(steps, 10),
+            res => panic!("Expected a step limit, got {:?}", res),
+        }
+    }
+}
@@ -6,9 +6,11 @@ extern crate image;
 #[macro_use] extern crate aoc_runner_derive;
 extern crate voca_rs;
 extern crate termion;
+extern crate rayon;
 
 pub mod intcode;
 pub mod advent_image;
+pub mod orbit_map;
 
 pub mod day1;
 pub mod day2;
@@ -0,0 +1,196 @@
+use crate::orbit_map::OrbitGraph;
+use std::collections::HashSet;
+
+/// What to draw besides the orbits themselves
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExportOptions {
+    highlight: Option<(String, String)>,
+}
+
+impl ExportOptions {
+    pub fn new() -> ExportOptions {
+        ExportOptions::default()
+    }
+
+    /// Marks the transfer path between two objects
+    pub fn with_highlight(&self, from: &str, to: &str) -> ExportOptions {
+        ExportOptions { highlight: Some((from.to_string(), to.to_string())) }
+    }
+
+    /// The day 6 part 2 path, from `YOU` to `SAN`
+    pub fn with_transfer_path(&self) -> ExportOptions {
+        self.with_highlight("YOU", "SAN")
+    }
+
+    pub fn highlight(&self) -> Option<(&str, &str)> {
+        self.highlight.as_ref().map(|(f, t)| (&f[..], &t[..]))
+    }
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl OrbitGraph {
+    /// Ids on the highlighted path, empty if there is none or the two
+    /// objects aren't connected
+    fn highlighted(&self, options: &ExportOptions) -> HashSet<usize> {
+        options.highlight()
+            .and_then(|(from, to)| self.path(from, to))
+            .map(|path| path.iter().filter_map(|n| self.id(n)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Graphviz digraph with an edge from each object to its satellites.
+    /// Highlighted objects and the edges between them are drawn in red.
+    ///
+    /// ```
+    /// use aoc_2019::day6::get_orbits_from_input;
+    /// use aoc_2019::orbit_map::OrbitGraph;
+    /// use aoc_2019::orbit_map::export::ExportOptions;
+    ///
+    /// let graph = OrbitGraph::from_orbits(&get_orbits_from_input(&"COM)B\nB)YOU\nCOM)SAN".to_string())).unwrap();
+    ///
+    /// assert_eq!(graph.to_dot(&ExportOptions::new()), "digraph orbits {
+    ///     \"COM\" -> \"B\";
+    ///     \"B\" -> \"YOU\";
+    ///     \"COM\" -> \"SAN\";
+    /// }
+    /// ");
+    ///
+    /// assert_eq!(graph.to_dot(&ExportOptions::new().with_transfer_path()), "digraph orbits {
+    ///     \"COM\" [color=red];
+    ///     \"B\" [color=red];
+    ///     \"YOU\" [color=red];
+    ///     \"SAN\" [color=red];
+    ///     \"COM\" -> \"B\" [color=red];
+    ///     \"B\" -> \"YOU\" [color=red];
+    ///     \"COM\" -> \"SAN\" [color=red];
+    /// }
+    /// ");
+    /// ```
+    pub fn to_dot(&self, options: &ExportOptions) -> String {
+        let highlighted = self.highlighted(options);
+        let mut dot = String::from("digraph orbits {\n");
+
+        for id in (0..self.len()).filter(|id| highlighted.contains(id)) {
+            dot.push_str(&format!("    {} [color=red];\n", quote(&self.names[id])));
+        }
+
+        for child in 0..self.len() {
+            if let Some(parent) = self.parent_id(child) {
+                let style = if highlighted.contains(&parent) && highlighted.contains(&child) { " [color=red]" } else { "" };
+
+                dot.push_str(&format!("    {} -> {}{};\n", quote(&self.names[parent]), quote(&self.names[child]), style));
+            }
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// One line per object, indented under the object it orbits. Objects on
+    /// the highlighted path are followed by ` *`.
+    ///
+    /// ```
+    /// use aoc_2019::day6::get_orbits_from_input;
+    /// use aoc_2019::orbit_map::OrbitGraph;
+    /// use aoc_2019::orbit_map::export::ExportOptions;
+    ///
+    /// let graph = OrbitGraph::from_orbits(&get_orbits_from_input(&"COM)B\nB)C\nC)YOU\nB)SAN\nCOM)D".to_string())).unwrap();
+    ///
+    /// assert_eq!(graph.to_tree(&ExportOptions::new().with_transfer_path()), "\
+    /// COM
+    /// |-- B *
+    /// |   |-- C *
+    /// |   |   `-- YOU *
+    /// |   `-- SAN *
+    /// `-- D
+    /// ");
+    /// ```
+    pub fn to_tree(&self, options: &ExportOptions) -> String {
+        let highlighted = self.highlighted(options);
+        let mut tree = String::new();
+        let mut seen = vec![false; self.len()];
+
+        // Each entry is an object, the prefix for its children and its own
+        // connector, iterated without recursion as inputs can be deep
+        let mut stack: Vec<(usize, String, &str)> = (0..self.len())
+            .filter(|id| self.parent_id(*id).is_none())
+            .rev()
+            .map(|id| (id, String::new(), ""))
+            .collect();
+
+        while let Some((id, prefix, connector)) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+
+            let mark = if highlighted.contains(&id) { " *" } else { "" };
+            tree.push_str(&format!("{}{}{}{}\n", prefix, connector, self.names[id], mark));
+
+            let child_prefix = match connector {
+                "|-- " => format!("{}|   ", prefix),
+                "`-- " => format!("{}    ", prefix),
+                _ => prefix,
+            };
+            let children = self.child_ids(id);
+
+            for (i, child) in children.iter().enumerate().rev() {
+                let connector = if i + 1 == children.len() { "`-- " } else { "|-- " };
+                stack.push((*child, child_prefix.clone(), connector));
+            }
+        }
+
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day6::{input_generator, orbits_to_nodes, find_root, traverse_children_until, path_diff, join_diff_paths, PathDiffFavor};
+    use std::fs;
+
+    #[test]
+    fn test_day6_path_matches_path_diff() {
+        let orbits = input_generator(&fs::read_to_string("input/2019/day6.txt").unwrap());
+        let graph = OrbitGraph::from_orbits(&orbits).unwrap();
+        let nodes = orbits_to_nodes(&orbits).unwrap();
+
+        let id = |name| nodes.iter().position(|n| n.name == name).unwrap();
+        let root = find_root(&nodes).unwrap();
+        let to_you = traverse_children_until(&nodes, root, id("YOU")).unwrap().unwrap();
+        let to_san = traverse_children_until(&nodes, root, id("SAN")).unwrap().unwrap();
+        let joined = join_diff_paths(
+            &path_diff(&to_you, &to_san, PathDiffFavor::First).unwrap(),
+            &path_diff(&to_you, &to_san, PathDiffFavor::Second).unwrap(),
+        ).unwrap();
+
+        assert_eq!(graph.path("YOU", "SAN").unwrap(), joined);
+    }
+
+    #[test]
+    fn test_day6_exports() {
+        let graph = OrbitGraph::from_orbits(&input_generator(&fs::read_to_string("input/2019/day6.txt").unwrap())).unwrap();
+        let options = ExportOptions::new().with_transfer_path();
+        let path_len = graph.path("YOU", "SAN").unwrap().len();
+
+        let tree = graph.to_tree(&options);
+        assert_eq!(tree.lines().count(), graph.len());
+        assert_eq!(tree.lines().filter(|l| l.ends_with(" *")).count(), path_len);
+
+        let dot = graph.to_dot(&options);
+        assert_eq!(dot.lines().filter(|l| l.contains(" -> ")).count(), graph.len() - 1);
+        assert_eq!(dot.lines().filter(|l| l.contains(" -> ") && l.ends_with("[color=red];")).count(), path_len - 1);
+    }
+
+    #[test]
+    fn test_unknown_highlight() {
+        let graph = OrbitGraph::from_orbits(&input_generator("COM)A")).unwrap();
+
+        assert_eq!(graph.to_tree(&ExportOptions::new().with_highlight("A", "B")), "COM\n`-- A\n");
+    }
+}
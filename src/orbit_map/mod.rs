@@ -0,0 +1,311 @@
+pub mod export;
+pub mod route;
+pub mod validate;
+
+use crate::day6::{Orbit, OrbitErr};
+use std::collections::{HashMap, VecDeque};
+
+/// Owned orbit tree, indexed by object name. Objects are numbered in the
+/// order they first appear in the orbits.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OrbitGraph {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+}
+
+impl OrbitGraph {
+    /// Fails with `OrbitErr::OrbitedDupe` if an object orbits two parents
+    ///
+    /// ```
+    /// use aoc_2019::day6::{get_orbits_from_input, OrbitErr};
+    /// use aoc_2019::orbit_map::OrbitGraph;
+    ///
+    /// let orbits = get_orbits_from_input(&"COM)B\nB)C\nB)D".to_string());
+    /// let graph = OrbitGraph::from_orbits(&orbits).unwrap();
+    ///
+    /// assert_eq!(graph.len(), 4);
+    /// assert_eq!(graph.parent("C"), Some("B"));
+    /// assert_eq!(graph.children("B"), vec!["C", "D"]);
+    /// assert_eq!(graph.roots(), vec!["COM"]);
+    ///
+    /// let twice = get_orbits_from_input(&"COM)B\nA)B".to_string());
+    /// assert_eq!(OrbitGraph::from_orbits(&twice), Err(OrbitErr::OrbitedDupe));
+    /// ```
+    pub fn from_orbits(orbits: &[Orbit]) -> Result<OrbitGraph, OrbitErr> {
+        let mut graph = OrbitGraph::default();
+
+        for orbit in orbits {
+            let parent = graph.insert(&orbit.orbited);
+            let child = graph.insert(&orbit.orbiter);
+
+            if graph.parents[child].is_some() {
+                return Err(OrbitErr::OrbitedDupe);
+            }
+
+            graph.parents[child] = Some(parent);
+            graph.children[parent].push(child);
+        }
+
+        Ok(graph)
+    }
+
+    fn insert(&mut self, name: &str) -> usize {
+        if let Some(id) = self.index.get(name) {
+            return *id;
+        }
+
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.index.insert(name.to_string(), id);
+        self.parents.push(None);
+        self.children.push(vec![]);
+
+        id
+    }
+
+    pub fn len(&self) -> usize { self.names.len() }
+
+    pub fn is_empty(&self) -> bool { self.names.is_empty() }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.index.get(name).cloned()
+    }
+
+    pub fn name(&self, id: usize) -> Option<&str> {
+        self.names.get(id).map(|n| &n[..])
+    }
+
+    pub fn names(&self) -> &[String] { &self.names }
+
+    pub fn parent_id(&self, id: usize) -> Option<usize> {
+        *self.parents.get(id)?
+    }
+
+    pub fn child_ids(&self, id: usize) -> &[usize] {
+        self.children.get(id).map(|c| &c[..]).unwrap_or(&[])
+    }
+
+    pub fn parent(&self, name: &str) -> Option<&str> {
+        self.name(self.parent_id(self.id(name)?)?)
+    }
+
+    pub fn children(&self, name: &str) -> Vec<&str> {
+        self.id(name)
+            .map(|id| self.child_ids(id).iter().filter_map(|c| self.name(*c)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Objects that don't orbit anything
+    pub fn roots(&self) -> Vec<&str> {
+        (0..self.len()).filter(|id| self.parents[*id].is_none()).filter_map(|id| self.name(id)).collect()
+    }
+
+    /// The object itself followed by everything it orbits, directly or not.
+    /// Stops if the parents loop back around.
+    pub fn ancestor_ids(&self, id: usize) -> Vec<usize> {
+        let mut path = vec![id];
+        let mut current = id;
+
+        while let Some(parent) = self.parent_id(current) {
+            if path.len() > self.len() {
+                break;
+            }
+            path.push(parent);
+            current = parent;
+        }
+
+        path
+    }
+
+    /// Number of direct and indirect orbits of an object
+    pub fn depth(&self, name: &str) -> Option<usize> {
+        Some(self.ancestor_ids(self.id(name)?).len() - 1)
+    }
+
+    /// Depth of every object reachable from a root, in a single pass
+    pub fn depths(&self) -> Vec<Option<usize>> {
+        let mut depths = vec![None; self.len()];
+        let mut queue: VecDeque<usize> = (0..self.len()).filter(|id| self.parents[*id].is_none()).collect();
+
+        for root in &queue {
+            depths[*root] = Some(0);
+        }
+
+        while let Some(id) = queue.pop_front() {
+            for child in self.child_ids(id) {
+                depths[*child] = depths[id].map(|d| d + 1);
+                queue.push_back(*child);
+            }
+        }
+
+        depths
+    }
+
+    /// Sum of all depths, the day 6 orbit count checksum
+    pub fn total_orbits(&self) -> usize {
+        self.depths().iter().filter_map(|d| *d).sum()
+    }
+
+    /// ```
+    /// use aoc_2019::day6::get_orbits_from_input;
+    /// use aoc_2019::orbit_map::OrbitGraph;
+    ///
+    /// let input = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN".to_string();
+    /// let graph = OrbitGraph::from_orbits(&get_orbits_from_input(&input)).unwrap();
+    ///
+    /// assert_eq!(graph.total_orbits(), 54);
+    /// assert_eq!(graph.depth("L"), Some(7));
+    /// assert_eq!(graph.lowest_common_ancestor("YOU", "SAN"), Some("D"));
+    /// assert_eq!(graph.lowest_common_ancestor("K", "L"), Some("K"));
+    /// assert_eq!(graph.subtree_size("E"), Some(6));
+    /// assert_eq!(graph.transfers("YOU", "SAN"), Some(4));
+    /// assert_eq!(graph.transfers("YOU", "nowhere"), None);
+    /// ```
+    pub fn lowest_common_ancestor(&self, a: &str, b: &str) -> Option<&str> {
+        let a_path = self.ancestor_ids(self.id(a)?);
+        let b_path = self.ancestor_ids(self.id(b)?);
+
+        a_path.iter().rev()
+            .zip(b_path.iter().rev())
+            .take_while(|(x, y)| x == y)
+            .last()
+            .and_then(|(id, _)| self.name(*id))
+    }
+
+    /// Number of objects in the tree rooted at `name`, itself included
+    pub fn subtree_size(&self, name: &str) -> Option<usize> {
+        let mut stack = vec![self.id(name)?];
+        let mut seen = vec![false; self.len()];
+
+        while let Some(id) = stack.pop() {
+            if !seen[id] {
+                seen[id] = true;
+                stack.extend(self.child_ids(id));
+            }
+        }
+
+        Some(seen.iter().filter(|s| **s).count())
+    }
+
+    /// Orbital transfers needed to move from the object `from` orbits to
+    /// the object `to` orbits. A root stands in for its own parent, and
+    /// objects in different trees can't be reached at all.
+    pub fn transfers(&self, from: &str, to: &str) -> Option<usize> {
+        let from = self.id(from)?;
+        let to = self.id(to)?;
+
+        self.distance(self.parent_id(from).unwrap_or(from), self.parent_id(to).unwrap_or(to))
+    }
+
+    /// Orbits crossed between two objects, through their lowest common
+    /// ancestor
+    fn distance(&self, a: usize, b: usize) -> Option<usize> {
+        let a_path = self.ancestor_ids(a);
+        let b_path = self.ancestor_ids(b);
+
+        let common = a_path.iter().rev()
+            .zip(b_path.iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count();
+
+        if common == 0 {
+            return None;
+        }
+
+        Some(a_path.len() - common + b_path.len() - common)
+    }
+
+    /// Objects from `from` up to the lowest common ancestor and back down to
+    /// `to`, both ends included. The same path `join_diff_paths` builds.
+    ///
+    /// ```
+    /// use aoc_2019::day6::get_orbits_from_input;
+    /// use aoc_2019::orbit_map::OrbitGraph;
+    ///
+    /// let input = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN".to_string();
+    /// let graph = OrbitGraph::from_orbits(&get_orbits_from_input(&input)).unwrap();
+    ///
+    /// assert_eq!(graph.path("YOU", "SAN"), Some(vec!["YOU", "K", "J", "E", "D", "I", "SAN"]));
+    /// assert_eq!(graph.path("L", "K"), Some(vec!["L", "K"]));
+    /// assert_eq!(graph.path("H", "H"), Some(vec!["H"]));
+    /// ```
+    pub fn path(&self, from: &str, to: &str) -> Option<Vec<&str>> {
+        let lca = self.id(self.lowest_common_ancestor(from, to)?)?;
+        let up = self.ancestor_ids(self.id(from)?);
+        let down = self.ancestor_ids(self.id(to)?);
+
+        let up_len = up.iter().position(|id| *id == lca)? + 1;
+        let down_len = down.iter().position(|id| *id == lca)?;
+
+        up[..up_len].iter()
+            .chain(down[..down_len].iter().rev())
+            .map(|id| self.name(*id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day6::{input_generator, orbits_to_nodes, calc_total_orbits};
+    use std::fs;
+
+    #[test]
+    fn test_day6() {
+        let orbits = input_generator(&fs::read_to_string("input/2019/day6.txt").unwrap());
+        let graph = OrbitGraph::from_orbits(&orbits).unwrap();
+        let nodes = orbits_to_nodes(&orbits).unwrap();
+
+        assert_eq!(graph.len(), nodes.len());
+        assert_eq!(graph.roots(), vec!["COM"]);
+        assert_eq!(graph.total_orbits() as u64, calc_total_orbits(&nodes).unwrap());
+        assert_eq!(graph.subtree_size("COM"), Some(graph.len()));
+    }
+
+    fn example() -> OrbitGraph {
+        let input = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN";
+
+        OrbitGraph::from_orbits(&input_generator(input)).unwrap()
+    }
+
+    #[test]
+    fn test_transfers_to_ancestor() {
+        let graph = example();
+
+        // From J, which K orbits, to K, which L orbits
+        assert_eq!(graph.transfers("K", "L"), Some(1));
+        assert_eq!(graph.transfers("L", "K"), Some(1));
+        assert_eq!(graph.transfers("YOU", "K"), Some(1));
+        assert_eq!(graph.transfers("YOU", "L"), Some(0));
+        assert_eq!(graph.transfers("COM", "B"), Some(0));
+        assert_eq!(graph.transfers("COM", "SAN"), Some(4));
+    }
+
+    #[test]
+    fn test_transfers_to_self() {
+        let graph = example();
+
+        assert_eq!(graph.transfers("YOU", "YOU"), Some(0));
+        assert_eq!(graph.transfers("COM", "COM"), Some(0));
+        assert_eq!(graph.transfers("YOU", "nowhere"), None);
+    }
+
+    #[test]
+    fn test_transfers_between_trees() {
+        let graph = OrbitGraph::from_orbits(&input_generator("COM)A\nX)Y")).unwrap();
+
+        assert_eq!(graph.transfers("A", "Y"), None);
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        let graph = OrbitGraph::from_orbits(&[Orbit::new("COM)A").unwrap()]).unwrap();
+
+        assert_eq!(graph.depth("B"), None);
+        assert_eq!(graph.parent("COM"), None);
+        assert!(graph.children("B").is_empty());
+        assert_eq!(graph.subtree_size("B"), None);
+    }
+}
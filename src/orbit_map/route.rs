@@ -0,0 +1,100 @@
+use crate::orbit_map::OrbitGraph;
+
+/// The objects passed through going from one object to another, both ends
+/// included, and the summed weight of the orbits between them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route<'a> {
+    pub path: Vec<&'a str>,
+    pub cost: u64,
+}
+
+impl Route<'_> {
+    /// Number of orbits crossed
+    pub fn hops(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+impl OrbitGraph {
+    /// Shortest route where every orbit costs 1. Orbits form a tree, so this
+    /// is the only route and `None` means the objects aren't connected.
+    ///
+    /// ```
+    /// use aoc_2019::day6::get_orbits_from_input;
+    /// use aoc_2019::orbit_map::OrbitGraph;
+    ///
+    /// let input = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN".to_string();
+    /// let graph = OrbitGraph::from_orbits(&get_orbits_from_input(&input)).unwrap();
+    ///
+    /// let route = graph.route("H", "F").unwrap();
+    /// assert_eq!(route.path, vec!["H", "G", "B", "C", "D", "E", "F"]);
+    /// assert_eq!(route.cost, 6);
+    ///
+    /// // Orbits around COM cost 10, the route from H to F never crosses one
+    /// let weighted = graph.route_weighted("H", "F", |orbited, _| if orbited == "COM" { 10 } else { 1 }).unwrap();
+    /// assert_eq!(weighted.cost, 6);
+    /// assert_eq!(graph.route_weighted("H", "COM", |orbited, _| if orbited == "COM" { 10 } else { 1 }).unwrap().cost, 12);
+    /// ```
+    pub fn route(&self, from: &str, to: &str) -> Option<Route<'_>> {
+        self.route_weighted(from, to, |_, _| 1)
+    }
+
+    /// Shortest route with each orbit costing `weight(orbited, orbiter)`,
+    /// whichever way it is crossed
+    pub fn route_weighted<F>(&self, from: &str, to: &str, weight: F) -> Option<Route<'_>>
+    where
+        F: Fn(&str, &str) -> u64,
+    {
+        let path = self.path(from, to)?;
+
+        let cost = path.windows(2).map(|pair| {
+            let (a, b) = (pair[0], pair[1]);
+
+            if self.parent(a) == Some(b) { weight(b, a) } else { weight(a, b) }
+        }).sum();
+
+        Some(Route { path, cost })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day6::input_generator;
+    use crate::orbit_map::OrbitGraph;
+    use std::collections::HashMap;
+    use std::fs;
+
+    #[test]
+    fn test_day6() {
+        let graph = OrbitGraph::from_orbits(&input_generator(&fs::read_to_string("input/2019/day6.txt").unwrap())).unwrap();
+        let route = graph.route("YOU", "SAN").unwrap();
+
+        // The route runs between YOU and SAN themselves, transfers between
+        // the objects they orbit
+        assert_eq!(route.hops(), graph.transfers("YOU", "SAN").unwrap() + 2);
+        assert_eq!(route.cost as usize, route.hops());
+        assert_eq!(graph.route("COM", "SAN").unwrap().hops(), graph.depth("SAN").unwrap());
+    }
+
+    #[test]
+    fn test_weights_table() {
+        let graph = OrbitGraph::from_orbits(&input_generator("COM)A\nA)B\nCOM)C")).unwrap();
+        let mut weights = HashMap::new();
+        weights.insert(("COM", "A"), 5);
+        weights.insert(("A", "B"), 7);
+
+        let weight = |orbited: &str, orbiter: &str| *weights.get(&(orbited, orbiter)).unwrap_or(&1);
+
+        assert_eq!(graph.route_weighted("B", "C", weight).unwrap().cost, 13);
+        assert_eq!(graph.route_weighted("C", "B", weight).unwrap().path, vec!["C", "COM", "A", "B"]);
+        assert_eq!(graph.route_weighted("B", "B", weight).unwrap().cost, 0);
+        assert_eq!(graph.route("B", "D"), None);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        let graph = OrbitGraph::from_orbits(&input_generator("COM)A\nX)Y")).unwrap();
+
+        assert_eq!(graph.route("A", "Y"), None);
+    }
+}
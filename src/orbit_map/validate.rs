@@ -0,0 +1,297 @@
+use crate::day6::Orbit;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A problem found in an orbit map. Line numbers start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    Unparseable { line: usize, text: String },
+    /// Objects that end up orbiting themselves, in orbit order
+    Cycle { names: Vec<String>, lines: Vec<usize> },
+    /// Every object that orbits nothing, unless that is exactly `COM`
+    Roots { roots: Vec<(String, usize)> },
+    /// An object and each of its parents with the line declaring it
+    TwoParents { name: String, parents: Vec<(String, usize)> },
+    /// The same orbit declared on more than one line, which `OrbitGraph`
+    /// rejects like any other second parent
+    RepeatedOrbit { orbited: String, orbiter: String, lines: Vec<usize> },
+    /// Objects with no connection at all to `COM`
+    Disconnected { names: Vec<String>, lines: Vec<usize> },
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+fn join_named(items: &[(String, usize)]) -> String {
+    items.iter().map(|(n, l)| format!("{} (line {})", n, l)).collect::<Vec<String>>().join(", ")
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Unparseable { line, text } => write!(f, "line {}: can't parse {:?}", line, text),
+            Issue::Cycle { names, lines } => {
+                write!(f, "cycle {} -> {} (lines {})", names.join(" -> "), names[0], join(lines))
+            },
+            Issue::Roots { roots } if roots.is_empty() => write!(f, "no object is orbiting nothing, expected COM"),
+            Issue::Roots { roots } => write!(f, "expected COM as the only root, found {}", join_named(roots)),
+            Issue::TwoParents { name, parents } => write!(f, "{} orbits more than one object: {}", name, join_named(parents)),
+            Issue::RepeatedOrbit { orbited, orbiter, lines } => {
+                write!(f, "{}){} is declared more than once (lines {})", orbited, orbiter, join(lines))
+            },
+            Issue::Disconnected { names, lines } => {
+                write!(f, "{} not connected to COM (lines {})", names.join(", "), join(lines))
+            },
+        }
+    }
+}
+
+struct Edge {
+    parent: usize,
+    child: usize,
+    line: usize,
+}
+
+#[derive(Default)]
+struct Names {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    /// Line each object first appears on
+    lines: Vec<usize>,
+}
+
+impl Names {
+    fn insert(&mut self, name: &str, line: usize) -> usize {
+        if let Some(id) = self.index.get(name) {
+            return *id;
+        }
+
+        self.names.push(name.to_string());
+        self.lines.push(line);
+        self.index.insert(name.to_string(), self.names.len() - 1);
+
+        self.names.len() - 1
+    }
+}
+
+/// Checks a whole orbit map, returning every issue found. An empty result
+/// means the map is a single tree rooted at `COM`.
+///
+/// ```
+/// use aoc_2019::orbit_map::validate::{validate, Issue};
+///
+/// assert!(validate("COM)B\nB)C\n").is_empty());
+///
+/// let issues = validate("COM)B\nB)C\nX)C\nD)E\nE)D");
+/// assert_eq!(issues[0].to_string(), "C orbits more than one object: B (line 2), X (line 3)");
+/// assert_eq!(issues[1], Issue::Cycle { names: vec!["D".to_string(), "E".to_string()], lines: vec![5, 4] });
+/// assert_eq!(issues[2].to_string(), "expected COM as the only root, found COM (line 1), X (line 3)");
+/// assert_eq!(issues[3].to_string(), "D, E not connected to COM (lines 4, 5)");
+/// ```
+pub fn validate(input: &str) -> Vec<Issue> {
+    let mut issues = vec![];
+    let mut names = Names::default();
+    let mut edges: Vec<Edge> = vec![];
+
+    for (i, text) in input.lines().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        match Orbit::new(text.trim()) {
+            Ok(orbit) => {
+                let parent = names.insert(&orbit.orbited, i + 1);
+                let child = names.insert(&orbit.orbiter, i + 1);
+
+                edges.push(Edge { parent, child, line: i + 1 });
+            },
+            Err(_) => issues.push(Issue::Unparseable { line: i + 1, text: text.to_string() }),
+        }
+    }
+
+    let count = names.names.len();
+    let mut parents: Vec<Vec<&Edge>> = (0..count).map(|_| vec![]).collect();
+    // Lines of every orbit declared more than once, keyed by its first line
+    let mut repeats: Vec<(&Edge, Vec<usize>)> = vec![];
+    for edge in &edges {
+        match parents[edge.child].iter().find(|e| e.parent == edge.parent) {
+            Some(first) => match repeats.iter_mut().find(|(f, _)| f.line == first.line) {
+                Some((_, lines)) => lines.push(edge.line),
+                None => repeats.push((first, vec![first.line, edge.line])),
+            },
+            None => parents[edge.child].push(edge),
+        }
+    }
+
+    for (first, lines) in repeats {
+        issues.push(Issue::RepeatedOrbit {
+            orbited: names.names[first.parent].clone(),
+            orbiter: names.names[first.child].clone(),
+            lines,
+        });
+    }
+
+    for (id, ps) in parents.iter().enumerate().filter(|(_, ps)| ps.len() > 1) {
+        issues.push(Issue::TwoParents {
+            name: names.names[id].clone(),
+            parents: ps.iter().map(|e| (names.names[e.parent].clone(), e.line)).collect(),
+        });
+    }
+
+    issues.extend(find_cycles(&names, &parents));
+
+    let roots: Vec<(String, usize)> = (0..count)
+        .filter(|id| parents[*id].is_empty())
+        .map(|id| (names.names[id].clone(), names.lines[id]))
+        .collect();
+    if roots.len() != 1 || roots[0].0 != "COM" {
+        issues.push(Issue::Roots { roots });
+    }
+
+    if let Some(com) = names.index.get("COM") {
+        issues.extend(find_disconnected(&names, &edges, *com));
+    }
+
+    issues
+}
+
+/// Depth first walk up every parent edge, reporting a cycle each time the
+/// walk reaches an object it is still above
+fn find_cycles(names: &Names, parents: &[Vec<&Edge>]) -> Vec<Issue> {
+    let count = names.names.len();
+    // 0 unvisited, 1 on the current walk, 2 done
+    let mut state = vec![0; count];
+    let mut issues = vec![];
+
+    for start in 0..count {
+        if state[start] != 0 {
+            continue;
+        }
+
+        // Each entry is an object and the index of the next parent to try
+        let mut walk = vec![(start, 0)];
+        state[start] = 1;
+
+        while let Some((id, next)) = walk.last_mut() {
+            let id = *id;
+            let edge = match parents[id].get(*next) {
+                Some(edge) => edge,
+                None => {
+                    state[id] = 2;
+                    walk.pop();
+                    continue;
+                },
+            };
+            *next += 1;
+
+            match state[edge.parent] {
+                0 => {
+                    state[edge.parent] = 1;
+                    walk.push((edge.parent, 0));
+                },
+                1 => {
+                    let from = walk.iter().position(|(w, _)| *w == edge.parent).unwrap_or(0);
+                    let cycle = &walk[from..];
+
+                    issues.push(Issue::Cycle {
+                        names: cycle.iter().map(|(w, _)| names.names[*w].clone()).collect(),
+                        lines: cycle.iter().map(|(w, n)| parents[*w][n - 1].line).collect(),
+                    });
+                },
+                _ => (),
+            }
+        }
+    }
+
+    issues
+}
+
+fn find_disconnected(names: &Names, edges: &[Edge], com: usize) -> Vec<Issue> {
+    let count = names.names.len();
+    let mut neighbours: Vec<Vec<usize>> = (0..count).map(|_| vec![]).collect();
+    for edge in edges {
+        neighbours[edge.parent].push(edge.child);
+        neighbours[edge.child].push(edge.parent);
+    }
+
+    let mut component = vec![None; count];
+    let mut next = 0;
+    for start in std::iter::once(com).chain(0..count) {
+        if component[start].is_some() {
+            continue;
+        }
+
+        let mut stack = vec![start];
+        component[start] = Some(next);
+        while let Some(id) = stack.pop() {
+            for n in &neighbours[id] {
+                if component[*n].is_none() {
+                    component[*n] = Some(next);
+                    stack.push(*n);
+                }
+            }
+        }
+        next += 1;
+    }
+
+    // Component 0 is the one containing COM
+    (1..next).map(|c| {
+        let members: Vec<usize> = (0..count).filter(|id| component[*id] == Some(c)).collect();
+        let mut lines: Vec<usize> = edges.iter().filter(|e| component[e.child] == Some(c)).map(|e| e.line).collect();
+        lines.sort();
+        lines.dedup();
+
+        Issue::Disconnected { names: members.iter().map(|id| names.names[*id].clone()).collect(), lines }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day6::{input_generator, OrbitErr};
+    use crate::orbit_map::OrbitGraph;
+    use std::fs;
+
+    #[test]
+    fn test_day6_is_valid() {
+        assert_eq!(validate(&fs::read_to_string("input/2019/day6.txt").unwrap()), vec![]);
+    }
+
+    #[test]
+    fn test_unparseable() {
+        assert_eq!(validate("COM)B\nB-C\n\nB)D"), vec![Issue::Unparseable { line: 2, text: "B-C".to_string() }]);
+    }
+
+    #[test]
+    fn test_roots() {
+        assert_eq!(validate("A)B"), vec![Issue::Roots { roots: vec![("A".to_string(), 1)] }]);
+
+        let issues = validate("COM)B\nB)COM");
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].to_string(), "cycle COM -> B -> COM (lines 2, 1)");
+        assert_eq!(issues[1].to_string(), "no object is orbiting nothing, expected COM");
+    }
+
+    #[test]
+    fn test_repeated_orbit() {
+        let input = "COM)B\nCOM)B\nB)C\nCOM)B";
+        let issues = validate(input);
+
+        assert_eq!(issues, vec![Issue::RepeatedOrbit { orbited: "COM".to_string(), orbiter: "B".to_string(), lines: vec![1, 2, 4] }]);
+        assert_eq!(issues[0].to_string(), "COM)B is declared more than once (lines 1, 2, 4)");
+        assert_eq!(OrbitGraph::from_orbits(&input_generator(input)), Err(OrbitErr::OrbitedDupe));
+    }
+
+    #[test]
+    fn test_cycle_hanging_off_tree() {
+        // The cycle goes through C's second parent
+        let issues = validate("COM)C\nC)B\nB)C");
+
+        assert!(issues.contains(&Issue::TwoParents {
+            name: "C".to_string(),
+            parents: vec![("COM".to_string(), 1), ("B".to_string(), 3)],
+        }));
+        assert!(issues.contains(&Issue::Cycle { names: vec!["C".to_string(), "B".to_string()], lines: vec![3, 2] }));
+    }
+}